@@ -0,0 +1,78 @@
+// The factory.txt grammar shared by every d10 part. One machine per line:
+//
+// [.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
+//
+// lights, then buttons listing the counters they touch, then the joltage
+// target of every counter.
+
+use std::fs;
+
+use crate::parse::{
+    ParseError, PResult, delimited, many, many1, map, one_of, parse_all, sep_by1, spaces1, tag, terminated, uint,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MachineSpec {
+    pub lights: Vec<bool>,
+    pub buttons: Vec<Vec<usize>>,
+    pub jolts: Vec<u16>,
+}
+
+pub fn parse_machine(line: &str, lineno: usize) -> PResult<MachineSpec> {
+    let lights = delimited(tag("["), many1(map(one_of(".#"), |c| c == '#')), tag("]"));
+    let button = delimited(tag("("), sep_by1(uint::<usize>(), tag(",")), tag(")"));
+    let jolts = delimited(tag("{"), sep_by1(uint::<u16>(), tag(",")), tag("}"));
+
+    let spec = parse_all(line.trim_end(), lineno, |c| {
+        let l = terminated(&lights, spaces1())(c)?;
+        let b = many(terminated(&button, spaces1()))(c)?;
+        let j = jolts(c)?;
+        Ok(MachineSpec { lights: l, buttons: b, jolts: j })
+    })?;
+
+    // a button can only touch a counter that exists
+    let width = spec.jolts.len().max(spec.lights.len());
+    for b in &spec.buttons {
+        if let Some(i) = b.iter().find(|&&i| i >= width) {
+            return Err(ParseError {
+                line: lineno,
+                col: 1,
+                expected: format!("button indexes below {}", width),
+                found: i.to_string(),
+            });
+        }
+    }
+    Ok(spec)
+}
+
+pub fn read_machines(path: &str) -> Vec<MachineSpec> {
+    let src = fs::read_to_string(path).expect("reading file failed");
+    src.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| parse_machine(l, i + 1).unwrap_or_else(|e| panic!("{}: {}", path, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_machine() {
+        let m = parse_machine("[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}\n", 1).unwrap();
+        assert_eq!(m.lights, vec![false, true, true, false]);
+        assert_eq!(m.buttons.len(), 6);
+        assert_eq!(m.buttons[1], vec![1, 3]);
+        assert_eq!(m.jolts, vec![3, 5, 4, 7]);
+    }
+
+    #[test]
+    fn test_parse_machine_errors() {
+        let e = parse_machine("[.##.] (3) (1;3) {3,5,4,7}", 7).unwrap_err();
+        assert_eq!((e.line, e.col), (7, 14));
+        let e = parse_machine("[.##.] (3) (9) {3,5,4,7}", 2).unwrap_err();
+        assert_eq!(e.found, "9");
+        assert!(parse_machine("[.##.] (3) {3,5,4,7", 1).is_err());
+    }
+}
@@ -1,86 +1,90 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use regex::Regex;
-
-// light has max 10 position
-// we map the positions into bit space as (2**n)
+mod input;
+
+#[path = "../../src/bitset.rs"]
+mod bitset;
+// only the GF(2) half is used here
+#[allow(dead_code)]
+#[path = "../../src/linalg.rs"]
+mod linalg;
+// input.rs only needs the list parsers
+#[allow(dead_code)]
+#[path = "../../src/parse.rs"]
+mod parse;
+// only do_bfs searches
+#[allow(dead_code)]
+#[path = "../../src/search.rs"]
+mod search;
+#[allow(dead_code)]
+#[path = "../../src/smallvec.rs"]
+mod smallvec;
+
+use bitset::BitSet;
+use linalg::Gf2Matrix;
+use search::SearchProblem;
+
+// we map light n to bit n of a set
 #[derive(Debug)]
 struct Machine {
-    lights: u16,
-    buttons: Vec<u16>,
-    start_cond: u16,
+    lights: BitSet,
+    buttons: Vec<BitSet>,
+    start_cond: BitSet,
     running: bool,
 }
 
 impl Machine {
     fn new() -> Self {
         Machine {
-            lights: 0,
-            buttons: Vec::<u16>::new(),
-            start_cond: 0,
+            lights: BitSet::new(),
+            buttons: Vec::<BitSet>::new(),
+            start_cond: BitSet::new(),
             running: false,
         }
     }
 
-    fn add_button(&mut self, b: u16) {
+    fn add_button(&mut self, b: BitSet) {
         self.buttons.push(b);
     }
 
-    fn set_start_condition(&mut self, s: u16) {
+    fn set_start_condition(&mut self, s: BitSet) {
         self.start_cond = s;
     }
 
     fn push_button(&mut self, b: usize) {
-        self.lights ^= self.buttons[b];
+        self.lights ^= &self.buttons[b];
     }
 
     fn do_bfs(&self) -> usize {
-        let mut map: HashMap<u16, HashSet<u16>> = HashMap::new();
-        // map.insert(0, HashSet::<u16>::new());
-        // map.insert(self.start_cond, HashSet::<u16>::new());
-        let mut frontier: HashSet<u16> = HashSet::from([0]);
-
-        let mut done = false;
-        let mut depth = 1;
-
-        loop {
-            let mut newfront: HashSet<u16> = HashSet::new();
-
-            for f in &frontier {
-                println!("Checking key {}", f);
-                if !map.contains_key(f) {
-                    // new node
-                    let mut edges = HashSet::new();
-                    for bdx in 0..self.buttons.len() {
-                        let e = self.buttons[bdx] ^ f;
-                        println!("Building edge {}", e);
-                        edges.insert(e.clone());
-                        newfront.insert(e.clone());
-                    }
-                    // edges.clone().into_iter().map(|x| newfront.insert(x));
-                    if edges.clone().into_iter().any(|x| x == self.start_cond) {
-                        println!("Found start! Depth: {}", depth);
-                        done = true;
-                        break;
-                    }
-                    map.insert(*f, edges);
-                } else {
-                    println!("{} already visited", f);
-                }
-            }
-            if done {
-                println!("Done!");
-                break;
-            }
-            if newfront.len() == 0 {
-                panic!("No frontier!");
-            }
-            depth += 1;
-            frontier = newfront.clone();
-            newfront.clear();
-        }
-        return depth;
+        // the lights start dark and we want the fewest presses to reach start_cond
+        let o = search::bfs(self);
+        println!("Searched {:?}", o.stats);
+        return o.steps().expect("start condition is unreachable");
+    }
+
+    fn do_linear(&self) -> usize {
+        // pressing a button twice undoes it so each button is pressed once
+        // or not at all, that is buttons * x = start_cond over GF(2) and we
+        // want the solution with the fewest presses
+        let width = self.buttons.iter().chain([&self.start_cond]).filter_map(|b| b.iter().max()).max().map_or(0, |m| m + 1);
+        let m = Gf2Matrix::from_columns(&self.buttons, width);
+        let s = m.solve(&self.start_cond).expect("start condition is unreachable");
+        println!("Rank {} with {} free buttons", m.rank(), s.null_space.len());
+        s.min_weight().len()
+    }
+}
+
+impl SearchProblem for Machine {
+    type State = BitSet;
+
+    fn start(&self) -> BitSet {
+        BitSet::new()
+    }
+
+    fn successors(&self, state: &BitSet) -> Vec<(BitSet, u64)> {
+        self.buttons.iter().map(|b| (b ^ state, 1)).collect()
+    }
+
+    fn is_goal(&self, state: &BitSet) -> bool {
+        *state == self.start_cond
     }
 }
 
@@ -91,52 +95,17 @@ impl Machine {
 //}
 
 fn main() {
-    let mut reader = BufReader::new(File::open("factory.txt").expect("reading file failed"));
-    let mut line = String::new();
-
     let mut machines = Vec::<Machine>::new();
 
-    let lre = Regex::new(r"\[[.#]+\]").expect("invalid regex");
-    let bre = Regex::new(r"\(\d(,\d)*\)").expect("invalid regex");
-    let jre = Regex::new(r"\{\d+(,\d+)*\}").expect("invalid regex");
-
-    loop {
-        match reader.read_line(&mut line) {
-            Ok(bytes) => {
-                if bytes == 0 {
-                    break;
-                }
-                let lights = lre.find(&line);
-                let buttons: Vec::<_> = bre.find_iter(&line).map(|m| m.as_str()).collect();
-                let jolts = jre.find(&line);
-                let mut m = Machine::new();
-                let mut l: u16 = 0;
-                for (i, c) in lights.unwrap().as_str().chars().enumerate() {
-                    if c == '#' {
-                        l |= 2_u16.pow((i-1).try_into().unwrap());
-                        println!("Found # l is {}", l);
-                    }
-                }
-                println!("Constructed start {} from {}", l, lights.unwrap().as_str());
-                m.start_cond = l;
-                for bt in buttons {
-                    let mut btn: u16 = 0;
-                    for c in bt.chars() {
-                        // error somewhere here how did 2,3 => 0d12 => 0b1100
-                        // we have to make sure our lights and buttons map to the same space
-                        match c {
-                            '0'..='9' => btn |= 2_u16.pow(c.to_digit(10).unwrap()),
-                            _ => (),
-                        }
-                    }
-                    println!("Constructed button {} from {}", btn, bt);
-                    m.buttons.push(btn);
-                }
-                machines.push(m);
-                line.clear();
-            }
-            Err(x) => panic!("{:?}", x),
+    for spec in input::read_machines("factory.txt") {
+        let mut m = Machine::new();
+        // light n is bit n, lights and buttons map to the same space
+        m.set_start_condition(spec.lights.iter().enumerate().filter(|(_, on)| **on).map(|(i, _)| i).collect());
+        for b in spec.buttons {
+            m.add_button(b.into_iter().collect());
         }
+        println!("Constructed {:?}", m);
+        machines.push(m);
     }
 
     let mut depths: Vec<usize> = Vec::new();
@@ -146,7 +115,9 @@ fn main() {
         for ibx in 0..m.buttons.len() {
             m.push_button(ibx);
         }
-        let d = m.do_bfs();
+        let d = m.do_linear();
+        // the search is slower but makes no assumptions, keep it as a check
+        assert_eq!(d, m.do_bfs(), "elimination and search disagree");
         depths.push(d);
         println!("Machine depth {}", d);
     }
@@ -159,27 +130,27 @@ mod tests {
 
     fn setup_machine() -> Machine {
         let mut m = Machine::new();
-        m.start_cond = 1;
-        m.buttons.push(1);
+        m.start_cond = BitSet::from(1);
+        m.buttons.push(BitSet::from(1));
         return m;
     }
 
     #[test]
     fn test_machine_lights() {
         let m = setup_machine();
-        assert_eq!(m.lights, 0);
+        assert_eq!(m.lights, BitSet::new());
     }
 
     #[test]
     fn test_machine_start_cond() {
         let m = setup_machine();
-        assert_eq!(m.start_cond, 1);
+        assert_eq!(m.start_cond, BitSet::from(1));
     }
 
     #[test]
     fn test_machine_button() {
         let m = setup_machine();
-        assert_eq!(m.buttons, vec![1]);
+        assert_eq!(m.buttons, vec![BitSet::from(1)]);
     }
 
     #[test]
@@ -192,15 +163,49 @@ mod tests {
     #[test]
     fn test_machine_multi_buttons() {
         let mut m = Machine::new();
-        m.start_cond = 12;
-        m.buttons = vec![0b100, 0b1, 0b1001];
+        m.start_cond = BitSet::from(12);
+        m.buttons = vec![0b100, 0b1, 0b1001].into_iter().map(BitSet::from).collect();
         m.push_button(0);
-        assert_eq!(m.lights, 0b100);
+        assert_eq!(m.lights, BitSet::from(0b100));
         m.push_button(1);
-        assert_eq!(m.lights, 0b101);
+        assert_eq!(m.lights, BitSet::from(0b101));
         m.push_button(2);
-        assert_eq!(m.lights, 0b1100);
+        assert_eq!(m.lights, BitSet::from(0b1100));
         assert_eq!(m.lights, m.start_cond);
 
     }
+
+    #[test]
+    fn test_machine_bfs() {
+        let mut m = Machine::new();
+        m.start_cond = BitSet::from(0b1100);
+        m.buttons = vec![0b100, 0b1, 0b1001].into_iter().map(BitSet::from).collect();
+        assert_eq!(m.do_bfs(), 3);
+        m.start_cond = BitSet::from(0b1000);
+        assert_eq!(m.do_bfs(), 2);
+    }
+
+    #[test]
+    fn test_machine_linear() {
+        // [.##.] (3) (1,3) (2) (2,3) (0,2) (0,1)
+        let mut m = Machine::new();
+        m.start_cond = [1, 2].into_iter().collect();
+        m.buttons = vec![vec![3], vec![1, 3], vec![2], vec![2, 3], vec![0, 2], vec![0, 1]]
+            .into_iter()
+            .map(|b| b.into_iter().collect())
+            .collect();
+        assert_eq!(m.do_linear(), 2);
+        assert_eq!(m.do_linear(), m.do_bfs());
+        m.start_cond = BitSet::from(0b1001);
+        assert_eq!(m.do_linear(), m.do_bfs());
+    }
+
+    #[test]
+    fn test_machine_wide() {
+        // more lights than fit in a u16
+        let mut m = Machine::new();
+        m.start_cond = [0, 20].into_iter().collect();
+        m.buttons = vec![[0, 20].into_iter().collect(), [3].into_iter().collect()];
+        assert_eq!(m.do_bfs(), 1);
+    }
 }
@@ -1,149 +1,92 @@
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 use std::iter::zip;
-use regex::Regex;
+
+mod input;
+
+#[path = "../../src/bitset.rs"]
+mod bitset;
+// input.rs only needs the list parsers
+#[allow(dead_code)]
+#[path = "../../src/parse.rs"]
+mod parse;
+#[allow(dead_code)]
+#[path = "../../src/search.rs"]
+mod search;
+#[allow(dead_code)]
+#[path = "../../src/smallvec.rs"]
+mod smallvec;
+
+use bitset::BitSet;
+use search::SearchProblem;
+use smallvec::Counters;
 
 #[derive(Debug)]
 struct Machine {
     jolts: Vec<u16>,
-    buttons: Vec<u16>,
+    buttons: Vec<BitSet>,
 }
 
 impl Machine {
     fn new() -> Self {
         Machine {
             jolts: Vec::<u16>::new(),
-            buttons: Vec::<u16>::new(),
+            buttons: Vec::<BitSet>::new(),
         }
     }
 
-    fn do_bfs(&self) -> usize {
-        fn overjolt(current: &Vec<u16>, target: &Vec<u16>) -> bool {
-            for (i, j) in zip(current, target) {
-                if i > j {
-                    return true;
-                }
-            }
-            return false;
+    fn expand_buttons(&self) -> Vec<Counters> {
+        // turn each button into a vector of increments, one per counter
+        let mut expb: Vec<Counters> = Vec::with_capacity(self.buttons.len());
+        for b in &self.buttons {
+            expb.push((0..self.jolts.len()).map(|i| b.contains(i) as u16).collect());
         }
+        return expb;
+    }
 
-        let mut map: HashSet<Vec<u16>> = HashSet::with_capacity(1024);
-        // we only need to check whether the node is already in the set
-        // and we carry the rest in the frontier
-        let mut frontier: HashSet<Vec<u16>> = HashSet::with_capacity(256);
-        let mut v = Vec::with_capacity(self.jolts.len());
-        for _ in 0..self.jolts.len() {
-            v.push(0);
-        }
-        frontier.insert(v);
+    fn do_bfs(&self) -> usize {
+        let problem = Presses { target: &self.jolts, buttons: self.expand_buttons() };
+        println!("Constructed buttons {:?}", problem.buttons);
+        let o = search::bfs(&problem);
+        println!("Searched {:?}", o.stats);
+        return o.steps().expect("jolt target is unreachable");
+    }
+}
+
+struct Presses<'a> {
+    target: &'a Vec<u16>,
+    buttons: Vec<Counters>,
+}
 
-        let mut newfront: HashSet<Vec<u16>> = HashSet::with_capacity(256);
-        let mut edges: HashSet<Vec<u16>> = HashSet::with_capacity(256);
+impl SearchProblem for Presses<'_> {
+    type State = Counters;
 
-        let mut done = false;
-        let mut depth = 1;
+    fn start(&self) -> Counters {
+        Counters::zeroed(self.target.len())
+    }
 
-        let mut expb: Vec<Vec<u16>> = Vec::with_capacity(self.buttons.len());
-        let mut btn: Vec<u16> = Vec::with_capacity(self.jolts.len());
+    fn successors(&self, state: &Counters) -> Vec<(Counters, u64)> {
+        self.buttons.iter().map(|b| (zip(state.iter(), b.iter()).map(|(i, j)| i + j).collect(), 1)).collect()
+    }
 
-        for bdx in 0..self.buttons.len() {
-            for i in 0..self.jolts.len() {
-                let j = (self.buttons[bdx] & 2_u16.pow(i.try_into().unwrap())) >> i;
-                // println!("Adding {} at pos {} to {:?}", j, i, ff);
-                btn.push(j);
-            }
-            expb.push(btn.clone());
-            btn.clear();
-        }
-        println!("Constructed buttons {:?}", expb);
-
-        loop {
-
-            for f in &frontier {
-                // println!("Checking key {:?}", f);
-                if !map.contains(f) {
-                    // new node
-                    if overjolt(f, &self.jolts) {
-                        // if any jolt is too high we stop exploring that path
-                        println!("{:?} is overjolt", f);
-                        map.insert(f.clone());
-                        continue;
-                    }
-                    for b in 0..expb.len() {
-                        let mut ff = f.clone();
-                        for i in 0..self.jolts.len() {
-                            ff[i] += expb[b][i];
-                        }
-                        // println!("Building edge {:?}", ff);
-                        edges.insert(ff.clone());
-                        newfront.insert(ff.clone());
-                    }
-                    if edges.clone().into_iter().any(|x| x == self.jolts) {
-                        println!("Found start! Depth: {}", depth);
-                        done = true;
-                        break;
-                    }
-                    edges.clear();
-                    map.insert(f.clone());
-                }
-            }
-            if done {
-                println!("Done!");
-                break;
-            }
-            if newfront.len() == 0 {
-                panic!("No frontier!");
-            }
-            depth += 1;
-            frontier = newfront.clone();
-            newfront.clear();
-        }
-        return depth;
+    fn is_goal(&self, state: &Counters) -> bool {
+        state[..] == self.target[..]
+    }
+
+    fn prune(&self, state: &Counters) -> bool {
+        // if any jolt is too high we stop exploring that path
+        zip(state.iter(), self.target).any(|(i, j)| i > j)
     }
 }
 
 fn main() {
-    let mut reader = BufReader::new(File::open("factory.txt").expect("reading file failed"));
-    let mut line = String::new();
-
     let mut machines = Vec::<Machine>::new();
 
-    let lre = Regex::new(r"\[[.#]+\]").expect("invalid regex");
-    let bre = Regex::new(r"\(\d(,\d)*\)").expect("invalid regex");
-    let jre = Regex::new(r"\{\d+(,\d+)*\}").expect("invalid regex");
-
-    loop {
-        match reader.read_line(&mut line) {
-            Ok(bytes) => {
-                if bytes == 0 {
-                    break;
-                }
-                let lights = lre.find(&line);
-                let buttons: Vec::<_> = bre.find_iter(&line).map(|m| m.as_str()).collect();
-                let jolts = jre.find(&line);
-                let mut m = Machine::new();
-                let mut l: u16 = 0;
-                for bt in buttons {
-                    let mut btn: u16 = 0;
-                    for c in bt.chars() {
-                        match c {
-                            '0'..='9' => btn |= 2_u16.pow(c.to_digit(10).unwrap()),
-                            _ => (),
-                        }
-                    }
-                    println!("Constructed button {} from {}", btn, bt);
-                    m.buttons.push(btn);
-                }
-                for j in jolts.unwrap().as_str().strip_prefix('{').unwrap().strip_suffix('}').unwrap().split(',') {
-                    println!("{}", j);
-                    m.jolts.push(j.parse::<u16>().unwrap());
-                }
-                machines.push(m);
-                line.clear();
-            }
-            Err(x) => panic!("{:?}", x),
+    for spec in input::read_machines("factory.txt") {
+        let mut m = Machine::new();
+        for b in spec.buttons {
+            m.buttons.push(b.into_iter().collect());
         }
+        m.jolts = spec.jolts;
+        machines.push(m);
     }
 
     let mut depths: Vec<usize> = Vec::new();
@@ -160,4 +103,13 @@ fn main() {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn test_machine_bfs() {
+        // (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
+        let mut m = Machine::new();
+        m.buttons = vec![0b1000, 0b1010, 0b100, 0b1100, 0b101, 0b11].into_iter().map(BitSet::from).collect();
+        m.jolts = vec![3, 5, 4, 7];
+        assert_eq!(m.do_bfs(), 10);
+    }
 }
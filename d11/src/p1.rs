@@ -1,9 +1,19 @@
 #![allow(warnings)]
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-use regex::{Match, Regex};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[path = "../../src/export.rs"]
+mod export;
+#[path = "../../src/memo.rs"]
+mod memo;
+#[path = "../../src/parse.rs"]
+mod parse;
+
+use export::Dot;
+use memo::Memo;
+use parse::{PResult, ident, many1, pair, parse_all, preceded, spaces1, tag};
 
 struct DG {
     nodes: HashMap<String, Vec<String>>,
@@ -25,69 +35,114 @@ impl DG {
         // start point is 'you'
         // find all paths to 'out'
 
-        let mut alist: HashSet<String> = HashSet::with_capacity(1024);
-
-        let mut paths: usize = 0;
+        // the number of paths from a node to a leaf never changes so we
+        // count each node once and reuse it
+        let mut memo: Memo<String, usize> = Memo::new();
 
-        fn descend(node: &String, nodes: &HashMap<String, Vec<String>>, alist: &mut HashSet<String>, paths: &mut usize) {
-            match nodes.get(node) {
-                Some(edges) => {
-                    println!("Found edges {:?}", edges);
-                    for n in edges {
-                        descend(n, nodes, alist, paths);
+        fn descend(node: &String, nodes: &HashMap<String, Vec<String>>, memo: &mut Memo<String, usize>) -> usize {
+            memo.get_or_compute(node.clone(), |memo| {
+                match nodes.get(node) {
+                    Some(edges) => {
+                        println!("Found edges {:?}", edges);
+                        edges.iter().map(|n| descend(n, nodes, memo)).sum()
+                    }
+                    None => {
+                        println!("Found leaf {}", node);
+                        1
                     }
                 }
-                None => {
-                    *paths += 1;
-                    println!("Found leaf {}", node);
-                }
-            }
+            })
         }
 
-        descend(&String::from("you"), &self.nodes, &mut alist, &mut paths);
+        let paths = descend(&String::from("you"), &self.nodes, &mut memo);
+        println!("Cache: {}", memo.stats());
         return paths;
     }
 }
 
-fn build_dg() -> DG {
-    let mut reader = BufReader::new(File::open("input.txt").expect("reading file failed"));
-    let mut line = String::new();
-
-    let label_re = Regex::new(r"^([a-z]+):").expect("invalid regex");
-    let output_re = Regex::new(r" ([a-z]+)").expect("invalid regex");
+fn parse_dg(src: &str) -> PResult<DG> {
+    // aaa: you hhh
+    let output = preceded(spaces1(), ident());
+    let device = pair(ident(), tag(":"), many1(output));
 
     let mut dg = DG::new();
+    for (i, line) in src.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (label, outputs) = parse_all(line.trim_end(), i + 1, &device)?;
+        dg.insert(label.to_string(), outputs.into_iter().map(|o| o.to_string()).collect());
+    }
+    return Ok(dg);
+}
 
-    loop {
-        match reader.read_line(&mut line) {
-            Ok(bytes) => {
-                if bytes == 0 {
-                    break;
-                }
-                let label = label_re.captures(&line).unwrap().get(1).unwrap().as_str();
-                // println!("Label: {}", label);
-                let outputs: Vec::<_> = output_re.find_iter(&line).map(|m: Match| m.as_str().trim().to_string()).collect();
-                // println!("Outputs {:?}", outputs);
-                dg.insert(label.to_string().clone(), outputs.clone());
-                line.clear();
+fn build_dg() -> DG {
+    let src = fs::read_to_string("input.txt").expect("reading file failed");
+    return parse_dg(&src).unwrap_or_else(|e| panic!("input.txt: {}", e));
+}
+
+impl DG {
+    fn to_dot(&self) -> Dot {
+        let mut dot = Dot::digraph();
+        dot.node("you", &[("color", "green"), ("style", "filled")]);
+        dot.node("out", &[("color", "red"), ("style", "filled")]);
+        let mut labels: Vec<&String> = self.nodes.keys().collect();
+        labels.sort();
+        for l in labels {
+            for n in &self.nodes[l] {
+                dot.edge(l, n, &[]);
             }
-            Err(x) => panic!("{:?}", x),
         }
+        dot
     }
-
-    return dg;
 }
 
 fn main() {
     let dg = build_dg();
     let paths = dg.solve();
     println!("There are {}", paths);
+
+    // p1 --export graph.dot
+    let args: Vec<String> = env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--export") {
+        let p = Path::new(args.get(i + 1).expect("--export needs a path"));
+        dg.to_dot().write(p).expect("writing graph failed");
+        println!("Wrote {}", p.display());
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_shared_paths() {
+        // you fans out to a and b which both lead through c
+        let mut dg = DG::new();
+        dg.insert("you".to_string(), vec!["a".to_string(), "b".to_string()]);
+        dg.insert("a".to_string(), vec!["c".to_string()]);
+        dg.insert("b".to_string(), vec!["c".to_string(), "out".to_string()]);
+        dg.insert("c".to_string(), vec!["out".to_string(), "out2".to_string()]);
+        assert_eq!(dg.solve(), 5);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let dg = parse_dg("you: bbb\nbbb: out\n").unwrap();
+        let dot = dg.to_dot().to_dot();
+        assert!(dot.contains("\"you\" -> \"bbb\";"));
+        assert!(dot.contains("\"bbb\" -> \"out\";"));
+    }
+
+    #[test]
+    fn test_parse_dg() {
+        let dg = parse_dg("you: bbb ccc\nbbb: out\n\nccc: out\n").unwrap();
+        assert_eq!(dg.nodes["you"], vec!["bbb", "ccc"]);
+        assert_eq!(dg.solve(), 2);
+        let e = parse_dg("you: bbb\nbbb out\n").err().unwrap();
+        assert_eq!((e.line, e.col), (2, 4));
+    }
+
     #[test]
     fn test_you_out() {
         let dg = build_dg();
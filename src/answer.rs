@@ -0,0 +1,423 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::str::FromStr;
+
+// An unsigned puzzle answer. Arithmetic stays on u64 while it fits and
+// moves to a BigUint once it would overflow, so a bigger input gives a
+// bigger answer instead of wrapping or panicking.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Answer {
+    Small(u64),
+    Big(BigUint),
+}
+
+impl Answer {
+    pub fn zero() -> Self {
+        Answer::Small(0)
+    }
+
+    pub fn one() -> Self {
+        Answer::Small(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Answer::Small(0)
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self {
+            Answer::Small(x) => Some(*x),
+            Answer::Big(_) => None,
+        }
+    }
+
+    // nearest f64, for scaling things like colours
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Answer::Small(x) => *x as f64,
+            Answer::Big(b) => b.to_string().parse::<f64>().unwrap_or(f64::INFINITY),
+        }
+    }
+
+    fn to_big(&self) -> BigUint {
+        match self {
+            Answer::Small(x) => BigUint::from(*x),
+            Answer::Big(b) => b.clone(),
+        }
+    }
+
+    // always hand back Small when the value fits so equality and hashing
+    // don't depend on how the value was computed
+    fn from_big(b: BigUint) -> Self {
+        match b.to_u64() {
+            Some(x) => Answer::Small(x),
+            None => Answer::Big(b),
+        }
+    }
+
+    // None when the result would be negative
+    pub fn checked_sub(&self, rhs: &Answer) -> Option<Answer> {
+        match (self, rhs) {
+            (Answer::Small(a), Answer::Small(b)) => a.checked_sub(*b).map(Answer::Small),
+            _ => self.to_big().checked_sub(&rhs.to_big()).map(Answer::from_big),
+        }
+    }
+}
+
+impl Default for Answer {
+    fn default() -> Self {
+        Answer::zero()
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(item: u64) -> Self {
+        Answer::Small(item)
+    }
+}
+
+impl From<u128> for Answer {
+    fn from(item: u128) -> Self {
+        Answer::from_big(BigUint::from(item))
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(item: usize) -> Self {
+        Answer::Small(item as u64)
+    }
+}
+
+impl Add<&Answer> for &Answer {
+    type Output = Answer;
+    fn add(self, rhs: &Answer) -> Answer {
+        if let (Answer::Small(a), Answer::Small(b)) = (self, rhs)
+            && let Some(x) = a.checked_add(*b)
+        {
+            return Answer::Small(x);
+        }
+        Answer::from_big(&self.to_big() + &rhs.to_big())
+    }
+}
+
+impl Mul<&Answer> for &Answer {
+    type Output = Answer;
+    fn mul(self, rhs: &Answer) -> Answer {
+        if let (Answer::Small(a), Answer::Small(b)) = (self, rhs)
+            && let Some(x) = a.checked_mul(*b)
+        {
+            return Answer::Small(x);
+        }
+        Answer::from_big(&self.to_big() * &rhs.to_big())
+    }
+}
+
+impl Add for Answer {
+    type Output = Answer;
+    fn add(self, rhs: Answer) -> Answer {
+        &self + &rhs
+    }
+}
+
+impl Mul for Answer {
+    type Output = Answer;
+    fn mul(self, rhs: Answer) -> Answer {
+        &self * &rhs
+    }
+}
+
+impl AddAssign<&Answer> for Answer {
+    fn add_assign(&mut self, rhs: &Answer) {
+        *self = &*self + rhs;
+    }
+}
+
+impl AddAssign<u64> for Answer {
+    fn add_assign(&mut self, rhs: u64) {
+        *self = &*self + &Answer::Small(rhs);
+    }
+}
+
+impl MulAssign<&Answer> for Answer {
+    fn mul_assign(&mut self, rhs: &Answer) {
+        *self = &*self * rhs;
+    }
+}
+
+impl Sum for Answer {
+    fn sum<I: Iterator<Item = Answer>>(iter: I) -> Answer {
+        iter.fold(Answer::zero(), |a, b| a + b)
+    }
+}
+
+impl Product for Answer {
+    fn product<I: Iterator<Item = Answer>>(iter: I) -> Answer {
+        iter.fold(Answer::one(), |a, b| a * b)
+    }
+}
+
+impl PartialOrd for Answer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Answer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Answer::Small(a), Answer::Small(b)) => a.cmp(b),
+            // a Big is always larger than any Small
+            (Answer::Small(_), Answer::Big(_)) => Ordering::Less,
+            (Answer::Big(_), Answer::Small(_)) => Ordering::Greater,
+            (Answer::Big(a), Answer::Big(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Small(x) => write!(f, "{}", x),
+            Answer::Big(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl FromStr for Answer {
+    type Err = ParseAnswerError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Answer::from_big(s.parse::<BigUint>()?))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAnswerError {
+    // byte offset of the first bad character
+    pub position: usize,
+}
+
+impl fmt::Display for ParseAnswerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid digit at position {}", self.position)
+    }
+}
+
+// Arbitrary precision unsigned integer as little endian base 2^32 limbs
+// with no trailing zero limbs. Only the operations answers need.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs.len() {
+            0 => Some(0),
+            1 => Some(self.limbs[0] as u64),
+            2 => Some(self.limbs[0] as u64 | (self.limbs[1] as u64) << 32),
+            _ => None,
+        }
+    }
+
+    fn mul_small_add(&mut self, m: u32, a: u32) {
+        let mut carry = a as u64;
+        for l in self.limbs.iter_mut() {
+            let x = *l as u64 * m as u64 + carry;
+            *l = x as u32;
+            carry = x >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    // divide in place and return the remainder
+    fn div_small(&mut self, d: u32) -> u32 {
+        let mut rem: u64 = 0;
+        for l in self.limbs.iter_mut().rev() {
+            let x = rem << 32 | *l as u64;
+            *l = (x / d as u64) as u32;
+            rem = x % d as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u32
+    }
+
+    pub fn checked_sub(&self, rhs: &BigUint) -> Option<BigUint> {
+        if *self < *rhs {
+            return None;
+        }
+        let mut out = Vec::with_capacity(self.limbs.len());
+        let mut borrow: i64 = 0;
+        for i in 0..self.limbs.len() {
+            let mut x = self.limbs[i] as i64 - borrow - *rhs.limbs.get(i).unwrap_or(&0) as i64;
+            borrow = 0;
+            if x < 0 {
+                x += 1 << 32;
+                borrow = 1;
+            }
+            out.push(x as u32);
+        }
+        Some(BigUint { limbs: out }.trim())
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(item: u64) -> Self {
+        BigUint { limbs: vec![item as u32, (item >> 32) as u32] }.trim()
+    }
+}
+
+impl From<u128> for BigUint {
+    fn from(item: u128) -> Self {
+        let limbs = (0..4).map(|i| (item >> (32 * i)) as u32).collect();
+        BigUint { limbs }.trim()
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let n = self.limbs.len().max(rhs.limbs.len());
+        let mut out = Vec::with_capacity(n + 1);
+        let mut carry: u64 = 0;
+        for i in 0..n {
+            let x = *self.limbs.get(i).unwrap_or(&0) as u64 + *rhs.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            out.push(x as u32);
+            carry = x >> 32;
+        }
+        out.push(carry as u32);
+        BigUint { limbs: out }.trim()
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        let mut out = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, &b) in rhs.limbs.iter().enumerate() {
+                let x = a as u64 * b as u64 + out[i + j] as u64 + carry;
+                out[i + j] = x as u32;
+                carry = x >> 32;
+            }
+            out[i + rhs.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs: out }.trim()
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // no trailing zeros so more limbs is always bigger
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // peel off nine decimal digits at a time
+        let mut n = self.clone();
+        let mut chunks = Vec::<u32>::new();
+        while !n.is_zero() {
+            chunks.push(n.div_small(1_000_000_000));
+        }
+        write!(f, "{}", chunks[chunks.len() - 1])?;
+        for c in chunks.iter().rev().skip(1) {
+            write!(f, "{:09}", c)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ParseAnswerError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseAnswerError { position: 0 });
+        }
+        let mut n = BigUint::default();
+        for (i, c) in s.bytes().enumerate() {
+            if !c.is_ascii_digit() {
+                return Err(ParseAnswerError { position: i });
+            }
+            n.mul_small_add(10, (c - b'0') as u32);
+        }
+        Ok(n.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_stays_small() {
+        let a = Answer::from(40u64) + Answer::from(2u64);
+        assert_eq!(a, Answer::Small(42));
+        assert_eq!(a.to_u64(), Some(42));
+    }
+
+    #[test]
+    fn test_add_promotes() {
+        let a = Answer::from(u64::MAX) + Answer::one();
+        assert!(matches!(a, Answer::Big(_)));
+        assert_eq!(a.to_string(), "18446744073709551616");
+        assert_eq!(a.checked_sub(&Answer::one()), Some(Answer::Small(u64::MAX)));
+        assert_eq!(a.to_f64(), 2f64.powi(64));
+    }
+
+    #[test]
+    fn test_product_promotes() {
+        let a: Answer = (0..5).map(|_| Answer::from(1_000_000_000u64)).product();
+        assert_eq!(a.to_string(), format!("1{}", "0".repeat(45)));
+        assert!(a > Answer::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_parse() {
+        let s = "123456789012345678901234567890";
+        let a: Answer = s.parse().unwrap();
+        assert_eq!(a.to_string(), s);
+        assert_eq!("987".parse::<Answer>(), Ok(Answer::Small(987)));
+        assert_eq!("9x7".parse::<Answer>(), Err(ParseAnswerError { position: 1 }));
+        assert_eq!("0".parse::<Answer>(), Ok(Answer::zero()));
+    }
+
+    #[test]
+    fn test_checked_sub() {
+        assert_eq!(Answer::one().checked_sub(&Answer::from(2u64)), None);
+        let big: Answer = "100000000000000000000000".parse().unwrap();
+        let less: Answer = "99999999999999999999999".parse().unwrap();
+        assert_eq!(big.checked_sub(&less), Some(Answer::one()));
+        assert_eq!(less.checked_sub(&big), None);
+    }
+}
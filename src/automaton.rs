@@ -0,0 +1,267 @@
+use std::fmt;
+
+use crate::export::{Frames, Image, Rgb};
+
+// A cellular automaton on a rectangular grid. A day picks the cell states,
+// which neighbours a cell sees and a rule mapping a cell and its
+// neighbours to the next state, then runs it for a number of steps or
+// until nothing changes.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Neighbourhood {
+    // the eight surrounding cells
+    Moore,
+    // the four orthogonal cells
+    VonNeumann,
+    Custom(Vec<(i32, i32)>),
+}
+
+impl Neighbourhood {
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        match self {
+            Neighbourhood::Moore => {
+                let mut v = Vec::new();
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        if (dx, dy) != (0, 0) {
+                            v.push((dx, dy));
+                        }
+                    }
+                }
+                v
+            }
+            Neighbourhood::VonNeumann => vec![(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Neighbourhood::Custom(v) => v.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    // every cell sees the grid as it was at the start of the step
+    Synchronous,
+    // cells are updated in reading order and later cells see the new
+    // state of earlier ones
+    InPlace,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepStats {
+    // 1-based
+    pub step: usize,
+    pub changed: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunStats {
+    // only the steps that changed something
+    pub steps: Vec<StepStats>,
+    // stopped because a step changed nothing rather than hitting the limit
+    pub fixed_point: bool,
+}
+
+impl RunStats {
+    pub fn changed(&self) -> usize {
+        self.steps.iter().map(|s| s.changed).sum()
+    }
+}
+
+type ColorFn<S> = Box<dyn Fn(&S) -> Rgb>;
+
+pub struct Automaton<S> {
+    width: usize,
+    height: usize,
+    cells: Vec<S>,
+    offsets: Vec<(i32, i32)>,
+    update: Update,
+    step: usize,
+    frames: Option<(Frames, usize, ColorFn<S>)>,
+}
+
+impl<S: Copy + Eq> Automaton<S> {
+    pub fn new(rows: Vec<Vec<S>>, neighbourhood: Neighbourhood, update: Update) -> Self {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |r| r.len());
+        assert!(rows.iter().all(|r| r.len() == width), "rows differ in length");
+        Automaton {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+            offsets: neighbourhood.offsets(),
+            update,
+            step: 0,
+            frames: None,
+        }
+    }
+
+    // one state per character, one row per line
+    pub fn parse(src: &str, state: impl Fn(char) -> S, neighbourhood: Neighbourhood, update: Update) -> Self {
+        let rows = src.lines().filter(|l| !l.is_empty()).map(|l| l.chars().map(&state).collect()).collect();
+        Self::new(rows, neighbourhood, update)
+    }
+
+    // write a frame of the starting grid and another after every step
+    // that changed something
+    pub fn record(&mut self, frames: Frames, scale: usize, color: impl Fn(&S) -> Rgb + 'static) {
+        self.frames = Some((frames, scale, Box::new(color)));
+        self.snapshot();
+    }
+
+    fn snapshot(&mut self) {
+        let img = match &self.frames {
+            Some((_, scale, color)) => self.image(*scale, color),
+            None => return,
+        };
+        if let Some((frames, _, _)) = self.frames.as_mut() {
+            let p = frames.push(&img).expect("writing frame failed");
+            println!("Wrote {}", p.display());
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> S {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn count(&self, s: S) -> usize {
+        self.cells.iter().filter(|c| **c == s).count()
+    }
+
+    pub fn rows(&self) -> Vec<Vec<S>> {
+        self.cells.chunks(self.width.max(1)).map(|r| r.to_vec()).collect()
+    }
+
+    pub fn image(&self, scale: usize, color: impl Fn(&S) -> Rgb) -> Image {
+        Image::from_grid(&self.rows(), scale, color)
+    }
+
+    fn neighbours(&self, src: &[S], i: usize, out: &mut Vec<S>) {
+        out.clear();
+        let (x, y) = ((i % self.width) as i32, (i / self.width) as i32);
+        for &(dx, dy) in &self.offsets {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx >= 0 && ny >= 0 && (nx as usize) < self.width && (ny as usize) < self.height {
+                out.push(src[ny as usize * self.width + nx as usize]);
+            }
+        }
+    }
+
+    // Apply the rule to every cell once. The rule gets the cell and the
+    // states of its neighbours that are on the grid.
+    pub fn step(&mut self, rule: impl Fn(S, &[S]) -> S) -> StepStats {
+        let mut buf = Vec::with_capacity(self.offsets.len());
+        let mut changed = 0;
+        match self.update {
+            Update::Synchronous => {
+                let before = self.cells.clone();
+                for i in 0..self.cells.len() {
+                    self.neighbours(&before, i, &mut buf);
+                    let next = rule(before[i], &buf);
+                    if next != before[i] {
+                        self.cells[i] = next;
+                        changed += 1;
+                    }
+                }
+            }
+            Update::InPlace => {
+                for i in 0..self.cells.len() {
+                    self.neighbours(&self.cells, i, &mut buf);
+                    let next = rule(self.cells[i], &buf);
+                    if next != self.cells[i] {
+                        self.cells[i] = next;
+                        changed += 1;
+                    }
+                }
+            }
+        }
+        self.step += 1;
+        if changed > 0 {
+            self.snapshot();
+        }
+        StepStats { step: self.step, changed }
+    }
+
+    // Step until nothing changes or `limit` steps have run
+    pub fn run(&mut self, rule: impl Fn(S, &[S]) -> S, limit: Option<usize>) -> RunStats {
+        let mut stats = RunStats::default();
+        while limit.is_none_or(|l| stats.steps.len() < l) {
+            let s = self.step(&rule);
+            if s.changed == 0 {
+                stats.fixed_point = true;
+                break;
+            }
+            stats.steps.push(s);
+        }
+        stats
+    }
+}
+
+impl<S: Copy + Eq + fmt::Debug> fmt::Debug for Automaton<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Automaton")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("update", &self.update)
+            .field("step", &self.step)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(c: bool, n: &[bool]) -> bool {
+        let live = n.iter().filter(|b| **b).count();
+        live == 3 || (c && live == 2)
+    }
+
+    #[test]
+    fn test_blinker() {
+        let mut a = Automaton::parse(".....\n..#..\n..#..\n..#..\n.....\n", |c| c == '#', Neighbourhood::Moore, Update::Synchronous);
+        assert_eq!((a.width(), a.height()), (5, 5));
+        let s = a.step(life);
+        assert_eq!(s, StepStats { step: 1, changed: 4 });
+        assert!(a.get(1, 2) && a.get(2, 2) && a.get(3, 2));
+        assert!(!a.get(2, 1));
+        // period two, never settles
+        let r = a.run(life, Some(4));
+        assert_eq!(r.steps.len(), 4);
+        assert!(!r.fixed_point);
+        assert_eq!(a.count(true), 3);
+    }
+
+    #[test]
+    fn test_in_place() {
+        // a cell turns on when the cell to its left is on, in place the
+        // whole row fills in one step
+        let rule = |c: bool, n: &[bool]| c || n.first() == Some(&true);
+        let left = Neighbourhood::Custom(vec![(-1, 0)]);
+        let mut a = Automaton::parse("#...\n", |c| c == '#', left.clone(), Update::InPlace);
+        let r = a.run(rule, None);
+        assert!(r.fixed_point);
+        assert_eq!(r.steps, vec![StepStats { step: 1, changed: 3 }]);
+        let mut a = Automaton::parse("#...\n", |c| c == '#', left, Update::Synchronous);
+        let r = a.run(rule, None);
+        assert_eq!(r.steps.len(), 3);
+        assert_eq!(r.changed(), 3);
+    }
+
+    #[test]
+    fn test_neighbourhoods() {
+        assert_eq!(Neighbourhood::Moore.offsets().len(), 8);
+        assert_eq!(Neighbourhood::VonNeumann.offsets().len(), 4);
+        // corners only see what is on the grid
+        let a = Automaton::parse("ab\ncd\n", |c| c, Neighbourhood::Moore, Update::Synchronous);
+        let mut buf = Vec::new();
+        a.neighbours(&a.cells, 0, &mut buf);
+        assert_eq!(buf, vec!['b', 'c', 'd']);
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+
+use crate::smallvec::SmallVec;
+
+// A set of small integers packed into u64 words. The first 128 bits live
+// inline and the set grows as needed past that, so puzzles are not tied
+// to the width of whatever integer we first packed them into.
+#[derive(Clone, Default)]
+pub struct BitSet {
+    words: SmallVec<u64, 2>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet { words: SmallVec::new() }
+    }
+
+    // room for bits 0..n without growing
+    pub fn with_capacity(n: usize) -> Self {
+        BitSet { words: SmallVec::zeroed(n.div_ceil(64)) }
+    }
+
+    fn grow(&mut self, words: usize) {
+        while self.words.len() < words {
+            self.words.push(0);
+        }
+    }
+
+    // the live words without any zero padding at the end, used so that
+    // two sets with different capacities still compare equal
+    fn trimmed(&self) -> &[u64] {
+        let mut n = self.words.len();
+        while n > 0 && self.words[n - 1] == 0 {
+            n -= 1;
+        }
+        &self.words[..n]
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        self.grow(i / 64 + 1);
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        if i / 64 < self.words.len() {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    pub fn toggle(&mut self, i: usize) {
+        self.grow(i / 64 + 1);
+        self.words[i / 64] ^= 1 << (i % 64);
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        match self.words.get(i / 64) {
+            Some(w) => w & (1 << (i % 64)) != 0,
+            None => false,
+        }
+    }
+
+    // popcount
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    pub fn clear(&mut self) {
+        for w in self.words.iter_mut() {
+            *w = 0;
+        }
+    }
+
+    // set bits in ascending order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { words: &self.words, idx: 0, cur: self.words.first().copied().unwrap_or(0) }
+    }
+
+    fn zip_with(&mut self, rhs: &BitSet, f: impl Fn(u64, u64) -> u64) {
+        self.grow(rhs.words.len());
+        for i in 0..self.words.len() {
+            let r = rhs.words.get(i).copied().unwrap_or(0);
+            self.words[i] = f(self.words[i], r);
+        }
+    }
+}
+
+pub struct Iter<'a> {
+    words: &'a [u64],
+    idx: usize,
+    cur: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.cur == 0 {
+            self.idx += 1;
+            if self.idx >= self.words.len() {
+                return None;
+            }
+            self.cur = self.words[self.idx];
+        }
+        let bit = self.cur.trailing_zeros() as usize;
+        // clear the lowest set bit
+        self.cur &= self.cur - 1;
+        Some(self.idx * 64 + bit)
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut b = BitSet::new();
+        for i in iter {
+            b.insert(i);
+        }
+        b
+    }
+}
+
+// the bits of a mask, bit n of the integer is element n of the set
+impl From<u64> for BitSet {
+    fn from(item: u64) -> Self {
+        let mut b = BitSet::new();
+        if item != 0 {
+            b.words.push(item);
+        }
+        b
+    }
+}
+
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed() == other.trimmed()
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.trimmed().hash(state);
+    }
+}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl BitXorAssign<&BitSet> for BitSet {
+    fn bitxor_assign(&mut self, rhs: &BitSet) {
+        self.zip_with(rhs, |a, b| a ^ b);
+    }
+}
+
+impl BitAndAssign<&BitSet> for BitSet {
+    fn bitand_assign(&mut self, rhs: &BitSet) {
+        self.zip_with(rhs, |a, b| a & b);
+    }
+}
+
+impl BitOrAssign<&BitSet> for BitSet {
+    fn bitor_assign(&mut self, rhs: &BitSet) {
+        self.zip_with(rhs, |a, b| a | b);
+    }
+}
+
+impl BitXor for &BitSet {
+    type Output = BitSet;
+    fn bitxor(self, rhs: &BitSet) -> BitSet {
+        let mut b = self.clone();
+        b ^= rhs;
+        b
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+    fn bitand(self, rhs: &BitSet) -> BitSet {
+        let mut b = self.clone();
+        b &= rhs;
+        b
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+    fn bitor(self, rhs: &BitSet) -> BitSet {
+        let mut b = self.clone();
+        b |= rhs;
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_contains() {
+        let mut b = BitSet::new();
+        b.insert(3);
+        b.insert(200);
+        assert!(b.contains(3));
+        assert!(b.contains(200));
+        assert!(!b.contains(4));
+        assert!(!b.contains(1000));
+        assert_eq!(b.len(), 2);
+        b.remove(200);
+        assert_eq!(b.len(), 1);
+        b.toggle(3);
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn test_iter() {
+        let b: BitSet = [130, 0, 64, 5].into_iter().collect();
+        assert_eq!(b.iter().collect::<Vec<_>>(), vec![0, 5, 64, 130]);
+        assert_eq!(BitSet::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_ops() {
+        let a = BitSet::from(0b1100);
+        let b: BitSet = [2, 70].into_iter().collect();
+        assert_eq!(&a ^ &b, [3, 70].into_iter().collect());
+        assert_eq!(&a & &b, BitSet::from(0b100));
+        assert_eq!((&a | &b).len(), 3);
+    }
+
+    #[test]
+    fn test_capacity_eq() {
+        // the same bits with different word counts are the same set
+        let mut a = BitSet::with_capacity(300);
+        a.insert(1);
+        assert_eq!(a, BitSet::from(0b10));
+        let mut b: BitSet = [1, 100].into_iter().collect();
+        b.remove(100);
+        assert_eq!(a, b);
+    }
+}
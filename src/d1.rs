@@ -1,70 +1,213 @@
 use std::fs;
 use std::io::{self, BufRead};
 
+use crate::answer::Answer;
+use crate::parse::{Cursor, PResult, int, one_of, opt, parse_all, spaces, tag, uint};
+
+// The dial the lock is built around. Every click that lands on one of the
+// targets scores a point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dial {
+    pub size: i64,
+    pub start: i64,
+    pub targets: Vec<i64>,
+}
+
+impl Dial {
+    pub fn new(size: i64, start: i64, targets: Vec<i64>) -> Result<Dial, String> {
+        if size < 1 {
+            return Err(format!("dial size {} must be at least 1", size));
+        }
+        if let Some(x) = [start].iter().chain(&targets).find(|x| !(0..size).contains(*x)) {
+            return Err(format!("mark {} is not on a dial of size {}", x, size));
+        }
+        // a target listed twice would score every click twice
+        if let Some((i, x)) = targets.iter().enumerate().find(|(i, x)| targets[..*i].contains(x)) {
+            return Err(format!("target {} is listed again at {}", x, i + 1));
+        }
+        Ok(Dial { size, start, targets })
+    }
+}
+
+impl Default for Dial {
+    fn default() -> Self {
+        Dial { size: 100, start: 50, targets: vec![0] }
+    }
+}
+
+// What a single instruction did to the dial. position is where the dial
+// stopped after the instruction, instruction counts from 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub instruction: usize,
+    pub position: i64,
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    Rotated { direction: char, distance: i64 },
+    // turned past 0 into size - 1, once per lap
+    WrappedLeft { times: u64 },
+    // turned past size - 1 into 0
+    WrappedRight { times: u64 },
+    // index is where target sits in dial.targets
+    HitTarget { index: usize, target: i64, times: u64 },
+}
+
 struct ComboLock {
-    position: i16,
-    points: u16,
+    dial: Dial,
+    // instructions twisted so far
+    instructions: usize,
+    position: i64,
+    // a few million big twists can go past a u64
+    points: Answer,
+    // points scored on each of dial.targets
+    hits: Vec<Answer>,
+    // the first password rule only counts twists that stop on a target
+    landings: Answer,
 }
 
 impl ComboLock {
-    fn new(x: i16) -> ComboLock {
-        return ComboLock {
-            position: x,
-            points: 0,
-        };
+    #[cfg(test)]
+    fn new(x: i64) -> ComboLock {
+        ComboLock::with_dial(Dial { start: x, ..Dial::default() })
     }
-    fn twist(&mut self, direction: char, distance: i16) {
-        match direction {
-            'L' => {
-                for _ in 0..distance {
-                    self.position -= 1;
-                    if self.position == -1 {
-                        println!("ROLLOVER-LEFT");
-                        self.position = 99;
-                    }
-                    if self.position == 0 {
-                        println!("ZERO");
-                        self.points += 1;
-                    }
-                }
-            }
-            'R' => {
-                for _ in 0..distance {
-                    self.position += 1;
-                    if self.position == 100 {
-                        println!("ROLLOVER-RIGHT");
-                        self.position = 0;
-                    }
-                    if self.position == 0 {
-                        println!("ZERO");
-                        self.points += 1;
-                    }
-                }
-            }
+
+    fn with_dial(dial: Dial) -> ComboLock {
+        ComboLock {
+            position: dial.start,
+            instructions: 0,
+            points: Answer::zero(),
+            hits: vec![Answer::zero(); dial.targets.len()],
+            landings: Answer::zero(),
+            dial,
+        }
+    }
+
+    // Turn the dial and return the new position with the number of clicks
+    // that landed on a target. A negative distance turns the other way.
+    #[cfg(test)]
+    fn twist(&mut self, direction: char, distance: i64) -> (i64, u64) {
+        self.twist_with(direction, distance, |_| {})
+    }
+
+    // twist, passing each thing that happened to on_event. A big twist
+    // gives one event per kind with a count rather than one per lap.
+    fn twist_with(&mut self, direction: char, distance: i64, mut on_event: impl FnMut(Event)) -> (i64, u64) {
+        // i128 so that no distance can overflow on the way
+        let step = match direction {
+            'L' => -(distance as i128),
+            'R' => distance as i128,
             _ => panic!("Direction must be L or R"),
         };
+        let p = self.position as i128;
+        let n = self.dial.size as i128;
+        let mut total = 0;
+        // every event reports where the dial stops
+        self.position = (p + step).rem_euclid(n) as i64;
+        let (instruction, position) = (self.instructions, self.position);
+        let mut emit = |kind| on_event(Event { instruction, position, kind });
+        emit(EventKind::Rotated { direction, distance });
+        // p is on the dial so the laps are how far p + step is off it
+        let laps = (p + step).div_euclid(n);
+        if laps > 0 {
+            emit(EventKind::WrappedRight { times: laps as u64 });
+        } else if laps < 0 {
+            emit(EventKind::WrappedLeft { times: laps.unsigned_abs() as u64 });
+        }
+        for (index, (t, hits)) in self.dial.targets.iter().zip(self.hits.iter_mut()).enumerate() {
+            // the clicks visit p+1..=p+step going right or p+step..=p-1
+            // going left, count the positions in there that are t mod n
+            let q = p - *t as i128;
+            let h = if step >= 0 {
+                (q + step).div_euclid(n) - q.div_euclid(n)
+            } else {
+                (q - 1).div_euclid(n) - (q + step - 1).div_euclid(n)
+            } as u64;
+            *hits += h;
+            total += h;
+            if h > 0 {
+                emit(EventKind::HitTarget { index, target: *t, times: h });
+            }
+        }
+        self.points += total;
+        if self.dial.targets.contains(&self.position) {
+            self.landings += 1;
+        }
+        self.instructions += 1;
+        (self.position, total)
+    }
+}
+
+// L68, r48, +3 or -7
+fn turn(c: &mut Cursor) -> PResult<(char, i64)> {
+    if let Some(dir) = opt(one_of("LRlr"))(c)? {
+        return Ok((dir.to_ascii_uppercase(), int::<i64>()(c)?));
+    }
+    match opt(one_of("+-"))(c)? {
+        Some('+') => Ok(('R', uint::<i64>()(c)?)),
+        Some(_) => Ok(('L', uint::<i64>()(c)?)),
+        None => Err(c.error("a turn like L68, r48, +3 or -7")),
     }
 }
 
-fn parse_line_noalloc(line: &str) -> Option<(char, i16)> {
-    let line = line.trim();
-    let mut iter = line.char_indices();
-    let (_, first_char) = iter.next()?;
-    let (next_char_idx, _) = iter.next().unwrap_or((line.len(), '\0'));
-    let rest = &line[next_char_idx..];
-    Some((first_char, rest.parse::<i16>().ok()?))
+// Any number of turns split by commas or spaces, # starts a comment and a
+// blank line is no turns. lineno is only for the error.
+fn parse_line(line: &str, lineno: usize) -> PResult<Vec<(char, i64)>> {
+    let body = line.split('#').next().unwrap().trim_end();
+    parse_all(body, lineno, |c| {
+        let mut turns = Vec::new();
+        spaces()(c)?;
+        while !c.is_eof() {
+            turns.push(turn(c)?);
+            let from = c.pos();
+            spaces()(c)?;
+            opt(tag(","))(c)?;
+            spaces()(c)?;
+            if c.pos() == from && !c.is_eof() {
+                return Err(c.error("a comma or space between turns"));
+            }
+        }
+        Ok(turns)
+    })
 }
 
-pub fn run() {
-    let mut c = ComboLock::new(50);
+pub fn run(dial: &Dial) {
+    let mut c = ComboLock::with_dial(dial.clone());
     let fp = fs::File::open("day1_input.txt").unwrap();
     //let fp = fs::File::open("test.txt").unwrap();
     let reader = io::BufReader::new(fp);
-    for line in reader.lines() {
-        let (dir, dist) = parse_line_noalloc(&line.unwrap()).unwrap();
-        c.twist(dir, dist);
-        println!("Password is {}", c.points);
+    // instruction that first hit each target
+    let mut first = vec![None; dial.targets.len()];
+    let (mut left, mut right) = (Answer::zero(), Answer::zero());
+    // clicks turned each way, a negative distance turns the other way
+    let (mut turned_left, mut turned_right) = (Answer::zero(), Answer::zero());
+    for (i, line) in reader.lines().enumerate() {
+        let turns = parse_line(&line.unwrap(), i + 1).unwrap_or_else(|e| panic!("day1_input.txt: {}", e));
+        for (dir, dist) in turns {
+            c.twist_with(dir, dist, |e| match e.kind {
+                EventKind::WrappedLeft { times } => left += times,
+                EventKind::WrappedRight { times } => right += times,
+                EventKind::HitTarget { index, .. } => {
+                    first[index].get_or_insert(e.instruction);
+                }
+                EventKind::Rotated { direction, distance } => match (direction == 'L') == (distance >= 0) {
+                    true => turned_left += distance.unsigned_abs(),
+                    false => turned_right += distance.unsigned_abs(),
+                },
+            });
+        }
+    }
+    for ((t, h), f) in c.dial.targets.iter().zip(&c.hits).zip(&first) {
+        match f {
+            Some(f) => println!("Target {} was hit {} times, first by instruction {}", t, h, f + 1),
+            None => println!("Target {} was never hit", t),
+        }
     }
+    println!("Turned {} clicks left and {} right", turned_left, turned_right);
+    println!("Wrapped left {} times and right {} times", left, right);
+    println!("Stopped on a target {} times", c.landings);
     println!("The password is {}", c.points);
 }
 
@@ -92,4 +235,219 @@ mod tests {
         c.twist('R', 1);
         assert_eq!(c.position, 0);
     }
+
+    // one click at a time, how the dial actually moves
+    fn twist_clicks(position: i64, direction: char, distance: i64) -> (i64, u64) {
+        let (mut p, mut zeros) = (position, 0);
+        let (step, n) = match (direction, distance < 0) {
+            ('L', false) | ('R', true) => (-1, distance.unsigned_abs()),
+            _ => (1, distance.unsigned_abs()),
+        };
+        for _ in 0..n {
+            p = (p + step).rem_euclid(100);
+            if p == 0 {
+                zeros += 1;
+            }
+        }
+        (p, zeros)
+    }
+
+    #[test]
+    fn test_zero_crossings() {
+        let mut c = ComboLock::new(50);
+        assert_eq!(c.twist('L', 68), (82, 1));
+        assert_eq!(c.twist('L', 30), (52, 0));
+        assert_eq!(c.twist('R', 48), (0, 1));
+        assert_eq!(c.twist('L', 5), (95, 0));
+        assert_eq!(c.twist('R', 1000), (95, 10));
+        assert_eq!(c.twist('L', -5), (0, 1));
+        assert_eq!(c.twist('L', 100), (0, 1));
+        assert_eq!(c.points, Answer::from(14u64));
+    }
+
+    #[test]
+    fn test_twist_matches_clicks() {
+        // xorshift so the cases are the same every run
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..2000 {
+            let start = (next() % 100) as i64;
+            let dir = if next() % 2 == 0 { 'L' } else { 'R' };
+            let dist = (next() % 1001) as i64 - 500;
+            let mut c = ComboLock::new(start);
+            assert_eq!(c.twist(dir, dist), twist_clicks(start, dir, dist), "{} {}{}", start, dir, dist);
+        }
+    }
+
+    // the old part one, position by arithmetic and a point for stopping
+    // on zero
+    fn legacy_landings(start: i64, twists: &[(char, i64)]) -> u64 {
+        let (mut p, mut ans) = (start, 0);
+        for &(dir, dist) in twists {
+            p = match dir {
+                'L' => (p - dist).rem_euclid(100),
+                _ => (p + dist).rem_euclid(100),
+            };
+            if p == 0 {
+                ans += 1;
+            }
+        }
+        ans
+    }
+
+    #[test]
+    fn test_both_rules() {
+        let run = |twists: &[(char, i64)]| {
+            let mut c = ComboLock::new(50);
+            for &(d, n) in twists {
+                c.twist(d, n);
+            }
+            (c.landings.to_u64().unwrap(), c.points.to_u64().unwrap())
+        };
+        // the puzzle example, 3 stops on zero and 6 clicks onto it
+        let sample = [('L', 68), ('L', 30), ('R', 48), ('L', 5), ('R', 60), ('L', 55), ('L', 1), ('L', 99), ('R', 14), ('L', 82)];
+        assert_eq!(run(&sample), (3, 6));
+        // 0, 0, 50, 1, 0, 0 then 0 after three laps. Standing still on
+        // zero is a stop but not a click.
+        let laps = [('R', 50), ('L', 100), ('R', 250), ('L', 49), ('L', 1), ('R', 0), ('L', 300)];
+        assert_eq!(run(&laps), (5, 8));
+        // never reaching zero, then passing it without stopping
+        assert_eq!(run(&[('R', 49), ('L', 98)]), (0, 0));
+        assert_eq!(run(&[('R', 51), ('L', 2), ('R', 1)]), (1, 3));
+
+        // random runs against both old solvers
+        let mut seed: u64 = 0x9e3779b97f4a7c15;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for _ in 0..200 {
+            let twists: Vec<(char, i64)> =
+                (0..50).map(|_| (if next() % 2 == 0 { 'L' } else { 'R' }, (next() % 400) as i64)).collect();
+            let (mut p, mut clicks) = (50, 0);
+            for &(d, n) in &twists {
+                let (np, z) = twist_clicks(p, d, n);
+                p = np;
+                clicks += z;
+            }
+            assert_eq!(run(&twists), (legacy_landings(50, &twists), clicks));
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        let mut c = ComboLock::with_dial(Dial::new(10, 3, vec![0, 5]).unwrap());
+        let mut seen = Vec::new();
+        c.twist_with('R', 12, |e| seen.push(e));
+        c.twist_with('L', 3, |e| seen.push(e));
+        c.twist_with('L', 3, |e| seen.push(e));
+        let ev = |instruction, position, kind| Event { instruction, position, kind };
+        assert_eq!(
+            seen,
+            vec![
+                ev(0, 5, EventKind::Rotated { direction: 'R', distance: 12 }),
+                ev(0, 5, EventKind::WrappedRight { times: 1 }),
+                ev(0, 5, EventKind::HitTarget { index: 0, target: 0, times: 1 }),
+                ev(0, 5, EventKind::HitTarget { index: 1, target: 5, times: 2 }),
+                ev(1, 2, EventKind::Rotated { direction: 'L', distance: 3 }),
+                ev(2, 9, EventKind::Rotated { direction: 'L', distance: 3 }),
+                ev(2, 9, EventKind::WrappedLeft { times: 1 }),
+                ev(2, 9, EventKind::HitTarget { index: 0, target: 0, times: 1 }),
+            ]
+        );
+
+        // a histogram of where the sample stops
+        let mut c = ComboLock::new(50);
+        let mut stops = [0; 100];
+        let mut first_zero = None;
+        for (i, &(d, n)) in [('L', 68), ('L', 30), ('R', 48), ('L', 5), ('R', 60), ('L', 55)].iter().enumerate() {
+            c.twist_with(d, n, |e| {
+                if let EventKind::HitTarget { .. } = e.kind {
+                    first_zero.get_or_insert(e.instruction);
+                }
+            });
+            assert_eq!(c.instructions, i + 1);
+            stops[c.position as usize] += 1;
+        }
+        assert_eq!(first_zero, Some(0));
+        assert_eq!((stops[0], stops[82], stops[95]), (2, 1, 1));
+
+        // laps of a big twist come as one event
+        let mut c = ComboLock::new(50);
+        let mut laps = 0;
+        c.twist_with('L', 1_000_000_050, |e| {
+            if let EventKind::WrappedLeft { times } = e.kind {
+                laps += times;
+            }
+        });
+        assert_eq!(laps, 10_000_000);
+    }
+
+    #[test]
+    fn test_big_twists() {
+        let mut c = ComboLock::new(50);
+        assert_eq!(c.twist('R', i64::MAX), (57, 92233720368547758));
+        assert_eq!(c.twist('L', i64::MAX), (50, 92233720368547758));
+        // past what a u64 password can hold
+        for _ in 0..200 {
+            c.twist('R', i64::MAX);
+        }
+        assert!(c.points > Answer::from(u64::MAX));
+    }
+
+    #[test]
+    fn test_dial() {
+        // a 10 click dial counting 0 and 5
+        let mut c = ComboLock::with_dial(Dial::new(10, 3, vec![0, 5]).unwrap());
+        // 5, 0, 5
+        assert_eq!(c.twist('R', 12), (5, 3));
+        // 0, 5, 0, 5, 0
+        assert_eq!(c.twist('L', 25), (0, 5));
+        assert_eq!(c.hits, vec![Answer::from(4u64), Answer::from(4u64)]);
+        assert_eq!(c.points, Answer::from(8u64));
+        assert!(Dial::new(10, 10, vec![0]).is_err());
+        assert!(Dial::new(10, 0, vec![-1]).is_err());
+        assert!(Dial::new(0, 0, vec![]).is_err());
+        assert_eq!(Dial::new(10, 0, vec![0, 5, 0]), Err("target 0 is listed again at 3".to_string()));
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line("L68\n", 1).unwrap(), vec![('L', 68)]);
+        assert_eq!(parse_line("R40000000000", 1).unwrap(), vec![('R', 40000000000)]);
+        let e = parse_line("R99999999999999999999", 7).unwrap_err();
+        assert_eq!((e.line, e.col), (7, 2));
+        assert!(e.expected.contains("i64"));
+        assert_eq!(parse_line("X5", 3).unwrap_err().line, 3);
+    }
+
+    #[test]
+    fn test_flexible_syntax() {
+        assert_eq!(parse_line("", 1).unwrap(), vec![]);
+        assert_eq!(parse_line("  # just a comment", 1).unwrap(), vec![]);
+        assert_eq!(
+            parse_line(" l68, R30 +48\t-5,r-2 # the rest # is ignored\r", 1).unwrap(),
+            vec![('L', 68), ('R', 30), ('R', 48), ('L', 5), ('R', -2)]
+        );
+        assert_eq!(parse_line("+9223372036854775807,", 1).unwrap(), vec![('R', i64::MAX)]);
+
+        // the column points at the bad token
+        let e = parse_line("L1, R2, Q3", 4).unwrap_err();
+        assert_eq!((e.line, e.col), (4, 9));
+        assert!(e.expected.contains("turn"));
+        let e = parse_line("L1 ,, R2", 2).unwrap_err();
+        assert_eq!((e.line, e.col), (2, 5));
+        let e = parse_line("L1R2", 6).unwrap_err();
+        assert_eq!((e.line, e.col), (6, 3));
+        assert!(e.expected.contains("comma"));
+        let e = parse_line("L1 -x", 1).unwrap_err();
+        assert_eq!(e.col, 5);
+    }
 }
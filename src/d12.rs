@@ -0,0 +1,181 @@
+use std::fs;
+
+use crate::parse::{ParseError, PResult, many1, pair, parse_all, preceded, sections, spaces1, tag, terminated, uint};
+use crate::shape::{Grid, Shape};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region {
+    width: usize,
+    height: usize,
+    // how many of each shape have to go in
+    counts: Vec<usize>,
+}
+
+fn parse_input(src: &str) -> PResult<(Vec<Shape>, Vec<Region>)> {
+    let header = terminated(uint::<usize>(), tag(":"));
+    let size = pair(uint::<usize>(), tag("x"), uint::<usize>());
+    let region = pair(size, tag(":"), many1(preceded(spaces1(), uint::<usize>())));
+
+    let mut shapes = Vec::new();
+    let mut regions = Vec::new();
+    for s in sections(src) {
+        let (lineno, first) = s.lines().next().unwrap();
+        if let Ok(i) = parse_all(first.trim_end(), lineno, &header) {
+            if i != shapes.len() {
+                return Err(ParseError {
+                    line: lineno,
+                    col: 1,
+                    expected: format!("shape {}", shapes.len()),
+                    found: i.to_string(),
+                });
+            }
+            let picture = s.text.split_once('\n').map_or("", |(_, p)| p);
+            let shape = Shape::parse(picture, lineno + 1)?;
+            // nothing to place, pack has no cell to anchor it by
+            if shape.area() == 0 {
+                return Err(ParseError {
+                    line: lineno,
+                    col: 1,
+                    expected: format!("at least one # in shape {}", i),
+                    found: "an empty picture".to_string(),
+                });
+            }
+            shapes.push(shape);
+            continue;
+        }
+        for (l, text) in s.lines() {
+            let ((width, height), counts) = parse_all(text.trim_end(), l, &region)?;
+            if counts.len() != shapes.len() {
+                return Err(ParseError {
+                    line: l,
+                    col: 1,
+                    expected: format!("{} shape counts", shapes.len()),
+                    found: counts.len().to_string(),
+                });
+            }
+            regions.push(Region { width, height, counts });
+        }
+    }
+    Ok((shapes, regions))
+}
+
+fn fits(shapes: &[Shape], region: &Region) -> bool {
+    let area: usize = shapes.iter().zip(&region.counts).map(|(s, n)| s.area() * n).sum();
+    if area > region.width * region.height {
+        return false;
+    }
+
+    // if every present can have its own bounding box there's nothing to pack
+    let presents: usize = region.counts.iter().sum();
+    let (bw, bh) = shapes
+        .iter()
+        .zip(&region.counts)
+        .filter(|(_, n)| **n > 0)
+        .map(|(s, _)| s.bbox())
+        .fold((1, 1), |a, b| (a.0.max(b.0), a.1.max(b.1)));
+    if presents <= (region.width / bw as usize) * (region.height / bh as usize) {
+        return true;
+    }
+
+    let turns: Vec<Vec<Shape>> = shapes.iter().map(|s| s.orientations()).collect();
+    let mut left = region.counts.clone();
+    let slack = region.width * region.height - area;
+    // every orientation is tried so the region can be turned, scanning
+    // along the short side keeps the ragged edge of the search small
+    let (w, h) = (region.width.min(region.height), region.width.max(region.height));
+    pack(&turns, &mut Grid::new(w, h), &mut left, slack, 0)
+}
+
+// Fill the grid in reading order. The first free cell is either the top
+// left cell of some remaining present or stays empty for good, which is
+// only allowed while there is slack left. Presents are counted per shape
+// so identical copies are never tried in every order.
+fn pack(turns: &[Vec<Shape>], grid: &mut Grid, left: &mut [usize], slack: usize, from: usize) -> bool {
+    if left.iter().all(|&n| n == 0) {
+        return true;
+    }
+    let Some(at) = grid.first_free(from) else {
+        return false;
+    };
+    let (x0, y0) = grid.cell(at);
+
+    for s in 0..turns.len() {
+        if left[s] == 0 {
+            continue;
+        }
+        for t in &turns[s] {
+            let (ax, ay) = t.anchor();
+            let origin = (x0 - ax, y0 - ay);
+            if !grid.place(t, origin) {
+                continue;
+            }
+            left[s] -= 1;
+            let ok = pack(turns, grid, left, slack, at + 1);
+            left[s] += 1;
+            grid.remove(t, origin);
+            if ok {
+                return true;
+            }
+        }
+    }
+
+    if slack > 0 {
+        grid.set((x0, y0), true);
+        let ok = pack(turns, grid, left, slack - 1, at + 1);
+        grid.set((x0, y0), false);
+        return ok;
+    }
+    false
+}
+
+pub fn run() {
+    let src = fs::read_to_string("presents.txt").expect("reading file failed");
+    let (shapes, regions) = parse_input(&src).unwrap_or_else(|e| panic!("presents.txt: {}", e));
+    println!("{} shapes {} regions", shapes.len(), regions.len());
+
+    let mut ans = 0;
+    for r in &regions {
+        let ok = fits(&shapes, r);
+        println!("{}x{} {:?} fits {}", r.width, r.height, r.counts, ok);
+        if ok {
+            ans += 1;
+        }
+    }
+    println!("Ans {}", ans);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "0:\n###\n##.\n##.\n\n1:\n###\n##.\n.##\n\n2:\n.##\n###\n##.\n\n3:\n##.\n###\n##.\n\n4:\n###\n#..\n###\n\n5:\n###\n.#.\n###\n\n4x4: 0 0 0 0 2 0\n12x5: 1 0 1 0 2 2\n12x5: 1 0 1 0 3 2\n";
+
+    #[test]
+    fn test_parse_input() {
+        let (shapes, regions) = parse_input(SAMPLE).unwrap();
+        assert_eq!(shapes.len(), 6);
+        assert_eq!(shapes[4].cells(), &[(0, 0), (0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 2)]);
+        assert_eq!(regions[1], Region { width: 12, height: 5, counts: vec![1, 0, 1, 0, 2, 2] });
+
+        let e = parse_input("0:\n#.#\n\n2:\n###\n").unwrap_err();
+        assert_eq!((e.line, e.expected.as_str()), (4, "shape 1"));
+        let e = parse_input("0:\n#.#\n\n4x4: 1 1\n").unwrap_err();
+        assert_eq!(e.line, 4);
+        let e = parse_input("0:\n#x#\n").unwrap_err();
+        assert_eq!((e.line, e.col), (2, 2));
+        let e = parse_input("0:\n#.#\n\n1:\n...\n...\n\n4x4: 1 1\n").unwrap_err();
+        assert_eq!((e.line, e.expected.as_str()), (4, "at least one # in shape 1"));
+        assert_eq!(parse_input("0:\n").unwrap_err().expected, "at least one # in shape 0");
+    }
+
+    #[test]
+    fn test_fits() {
+        let (shapes, regions) = parse_input(SAMPLE).unwrap();
+        let ok: Vec<bool> = regions.iter().map(|r| fits(&shapes, r)).collect();
+        assert_eq!(ok, vec![true, true, false]);
+        // too little room no matter how they are turned
+        assert!(!fits(&shapes, &Region { width: 3, height: 3, counts: vec![0, 0, 0, 0, 2, 0] }));
+        // room for a box each
+        assert!(fits(&shapes, &Region { width: 6, height: 3, counts: vec![1, 1, 0, 0, 0, 0] }));
+    }
+}
@@ -1,141 +1,223 @@
+use std::cmp::Ordering;
+use std::fmt;
 use std::fs;
-use std::io::{self, BufRead};
+use std::str::FromStr;
+
+use crate::answer::Answer;
+use crate::digits;
+use crate::interval::IntervalSet;
+use crate::parse::{Cursor, PResult, opt, tag, uint};
 
 // we know clone is not fast
 #[derive(Debug, Clone)]
 struct VecInt {
     // A structure that represents an integer string
     // as a vector of characters
-    // In order to store a u64 as a decimal set of characters we need
-    // 20 positions + 1 for string terminator read from buf unless we decide
-    // to omit the terminator
+    // Always ascii decimal digits without leading zeros, so any length
+    // fits and equal numbers have equal bufs
     buf: Vec<u8>,
 }
 
-impl VecInt {
-    fn _increment(&mut self, i: usize) {
-        //println!("In self_increment");
-        match self.buf[i] {
-            // 48 == b'0'
-            // 57 == b'9'
-            48 ..= 56 => {
-                self.buf[i] += 1;
-                //println!("Adding 1");
-            }
-            57 => {
-                self.buf[i] = 48;
-                if i == 0 {
-                    self.buf.insert(0, b'0');
-                    self._increment(i);
-                } else {
-                    self._increment(i - 1);
-                }
-            }
-            _ => {
-                panic!("Unexpected");
-            }
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VecIntError {
+    Empty,
+    // at is the index into the input
+    NotADigit { at: usize, found: u8 },
+    // too many digits for the integer asked for
+    Overflow,
+}
+
+impl fmt::Display for VecIntError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VecIntError::Empty => write!(f, "no digits"),
+            VecIntError::NotADigit { at, found } => write!(f, "{:?} at {} is not a digit", *found as char, at),
+            VecIntError::Overflow => write!(f, "number too large"),
         }
     }
+}
+
+impl std::error::Error for VecIntError {}
 
+impl VecInt {
     fn increment(&mut self) {
-        self._increment(self.buf.len() - 1);
+        let mut d = digits::from_ascii(&self.buf).expect("not a decimal number");
+        digits::increment(&mut d, 10);
+        self.buf = digits::to_ascii(&d);
     }
 
-    #[allow(dead_code)]
-    fn invalid_one(&self) -> bool {
-        // part one
-        match self.buf.len() % 2 {
-            0 => {
-                let (f, l) = self.buf.split_at(self.buf.len() / 2);
-                f == l
-            }
-            1 => false,
-            _ => panic!("That was unexpected"),
+    // self - other as digit values, other must not be bigger
+    fn minus(&self, other: &VecInt) -> Vec<u8> {
+        // least significant first while subtracting
+        let a = digits::from_ascii(&self.buf).unwrap().into_iter().rev();
+        let mut b = digits::from_ascii(&other.buf).unwrap().into_iter().rev();
+        let mut borrow = 0;
+        let mut d: Vec<u8> = a
+            .map(|x| {
+                let v = x as i8 - b.next().unwrap_or(0) as i8 - borrow;
+                borrow = (v < 0) as i8;
+                (v + 10 * borrow) as u8
+            })
+            .collect();
+        while d.len() > 1 && d.last() == Some(&0) {
+            d.pop();
         }
+        d.reverse();
+        d
     }
 
+    // false if it was zero and is left alone
+    fn decrement(&mut self) -> bool {
+        let mut d = digits::from_ascii(&self.buf).expect("not a decimal number");
+        let r = digits::decrement(&mut d, 10);
+        self.buf = digits::to_ascii(&d);
+        r
+    }
 
-    fn invalid_two(&self) -> bool {
-        // invalid if any sequence of digits repeats at least twice
-        // e.g. 111 => '1' x3
-        // 1212 => '12' x2
-        // 12341234 => '1234' x2
+    #[cfg(test)]
+    fn is_invalid(&self, rule: &InvalidRule) -> bool {
+        rule.matches(&digits::from_ascii(&self.buf).expect("not a decimal number"))
+    }
+}
 
-        // iterate whole buf to check pat match
-        // pat length must be multiple of string len so we can 
-        // short-circuit if that check does not pass
-        // e.g. a string of length 6 could have a pattern of 1, 2, or 3
-        // a string of length 7 can only have a pattern of one
+// Which ids are invalid, judged on their digits in some base
+#[derive(Debug, Clone)]
+pub enum InvalidRule {
+    // exactly k copies of a block, part one is Repeat(2)
+    // e.g. 6464 => '64' x2
+    Repeat(usize),
+    // k or more copies of a block, part two is RepeatAtLeast(2)
+    // e.g. 111 => '1' x3
+    // 12341234 => '1234' x2
+    RepeatAtLeast(usize),
+    Palindrome,
+    // gets the digit values, most significant first. --rule has no way
+    // to name a function so only code builds one.
+    #[allow(dead_code)]
+    Custom(fn(&[u8]) -> bool),
+}
 
-        if self.buf.len() == 1 {
-            return false;
+impl InvalidRule {
+    pub fn matches(&self, d: &[u8]) -> bool {
+        match self {
+            InvalidRule::Repeat(k) => digits::repeats(d, *k),
+            InvalidRule::RepeatAtLeast(k) => ((*k).max(1)..=d.len()).any(|t| digits::repeats(d, t)),
+            InvalidRule::Palindrome => d.iter().eq(d.iter().rev()),
+            InvalidRule::Custom(f) => f(d),
         }
+    }
 
-        fn factor(n: usize) -> Vec<usize> {
-            // we don't do any fancy factoring because
-            // we're only dealing with numbers up to 20
-            let mut f = Vec::<usize>::new();
-            f.push(1);
-            for i in 2..n {
-                if n % i == 0 {
-                    f.push(i);
-                }
+    // the sum of the invalid ids in lo..=hi
+    pub fn sum(&self, lo: u64, hi: u64, base: u32) -> Answer {
+        match self {
+            InvalidRule::Repeat(k) => {
+                sum_periodic(lo, hi, base as u64, |len, p| len.is_multiple_of(*k) && (len / k).is_multiple_of(p))
             }
-            return f;
-        }
-
-        let check = |n: usize| -> bool {
-            //println!("Checking {}", n);
-            let pat = &self.buf[0..n];
-            let mut i = n;
-            //println!("Starting at {}", n);
-            while i <= self.buf.len() - n {
-                if self.buf[i..i+n] != *pat {
-                    //println!("FAILED PATTERN MATCH");
-                    return false;
+            InvalidRule::RepeatAtLeast(k) => sum_periodic(lo, hi, base as u64, |len, p| len / p >= *k),
+            InvalidRule::Palindrome => sum_palindromes(lo, hi, base),
+            // nothing to go on but the predicate, so try every id
+            InvalidRule::Custom(_) => {
+                let mut total = Answer::zero();
+                for x in lo..=hi {
+                    if self.matches(&digits::to_digits(x, base)) {
+                        total += x;
+                    }
                 }
-                i += n;
+                total
             }
-            //println!("SUCCESSFUL PATTERN MATCH");
-            return true;
-        };
+        }
+    }
+}
+
+// repeat:2, repeat-at-least:2 or palindrome
+impl FromStr for InvalidRule {
+    type Err = String;
 
-        let nf = factor(self.buf.len());
-        //println!("Factors of {}: {:?}", self.buf.len(), nf);
-        nf.into_iter().map(|x| check(x)).any(|x| x == true)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let k = |n: &str| match n.parse::<usize>() {
+            Ok(k) if k > 0 => Ok(k),
+            _ => Err(format!("repeat count {:?} must be a number above 0", n)),
+        };
+        match s.split_once(':') {
+            Some(("repeat", n)) => Ok(InvalidRule::Repeat(k(n)?)),
+            Some(("repeat-at-least", n)) => Ok(InvalidRule::RepeatAtLeast(k(n)?)),
+            None if s == "palindrome" => Ok(InvalidRule::Palindrome),
+            _ => Err(format!("unknown rule {:?}, try repeat:K, repeat-at-least:K or palindrome", s)),
+        }
     }
 }
 
-impl From<&[u8]> for VecInt {
-    fn from(item: &[u8]) -> VecInt {
-        let mut buf = Vec::<u8>::with_capacity(20);
-        let mut iter = item.into_iter().enumerate();
-        while let Some((i, c)) = iter.next() {
-            buf[i] = *c;
+impl fmt::Display for InvalidRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidRule::Repeat(k) => write!(f, "repeat:{}", k),
+            InvalidRule::RepeatAtLeast(k) => write!(f, "repeat-at-least:{}", k),
+            InvalidRule::Palindrome => write!(f, "palindrome"),
+            InvalidRule::Custom(_) => write!(f, "custom"),
         }
-        VecInt { buf: buf }
     }
 }
 
-impl From<Vec<u8>> for VecInt {
-    fn from(item: Vec<u8>) -> VecInt {
-        let mut buf = item.clone();
-        if buf[buf.len() - 1] == b'\0' {
-            let _ = buf.pop();
+impl From<u64> for VecInt {
+    fn from(n: u64) -> VecInt {
+        VecInt { buf: digits::to_ascii(&digits::to_digits(n, 10)) }
+    }
+}
+
+impl From<u128> for VecInt {
+    fn from(n: u128) -> VecInt {
+        VecInt { buf: n.to_string().into_bytes() }
+    }
+}
+
+impl TryFrom<&[u8]> for VecInt {
+    type Error = VecIntError;
+
+    fn try_from(item: &[u8]) -> Result<VecInt, VecIntError> {
+        if item.is_empty() {
+            return Err(VecIntError::Empty);
         }
-        while buf.len() < 20 {
-            buf.insert(0, 0);
+        if let Some(at) = item.iter().position(|c| !c.is_ascii_digit()) {
+            return Err(VecIntError::NotADigit { at, found: item[at] });
         }
-        VecInt { buf: item }
+        // 007 is 7 but 0 stays 0
+        let lead = item.iter().take_while(|&&c| c == b'0').count().min(item.len() - 1);
+        Ok(VecInt { buf: item[lead..].to_vec() })
+    }
+}
+
+impl TryFrom<Vec<u8>> for VecInt {
+    type Error = VecIntError;
+
+    fn try_from(item: Vec<u8>) -> Result<VecInt, VecIntError> {
+        VecInt::try_from(&item[..])
     }
 }
 
-impl Into<u64> for VecInt {
-    fn into(self) -> u64 {
-        // a u64 may have up to 20 decimal characters
-        // plus one for string terminator
-        //println!("Try to parse {:?}", self.buf);
-        str::from_utf8(&self.buf).unwrap().parse::<u64>().unwrap()
+impl TryFrom<&str> for VecInt {
+    type Error = VecIntError;
+
+    fn try_from(item: &str) -> Result<VecInt, VecIntError> {
+        VecInt::try_from(item.as_bytes())
+    }
+}
+
+impl TryFrom<&VecInt> for u64 {
+    type Error = VecIntError;
+
+    fn try_from(v: &VecInt) -> Result<u64, VecIntError> {
+        digits::from_digits(&digits::from_ascii(&v.buf).unwrap(), 10).ok_or(VecIntError::Overflow)
+    }
+}
+
+impl TryFrom<&VecInt> for u128 {
+    type Error = VecIntError;
+
+    fn try_from(v: &VecInt) -> Result<u128, VecIntError> {
+        v.buf
+            .iter()
+            .try_fold(0u128, |n, c| n.checked_mul(10)?.checked_add((c - b'0') as u128))
+            .ok_or(VecIntError::Overflow)
     }
 }
 
@@ -147,16 +229,71 @@ impl PartialEq for VecInt {
 
 impl Eq for VecInt {}
 
+// no leading zeros, so the longer number is bigger and equal lengths
+// compare digit by digit
+impl Ord for VecInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.buf.len().cmp(&other.buf.len()).then_with(|| self.buf.cmp(&other.buf))
+    }
+}
+
+impl PartialOrd for VecInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 #[derive(Debug, Clone)]
 struct VecIntRange {
     // A range of struct::VecInt that implements iterator
+    // start..=stop, empty once start passes stop
     start: VecInt,
     stop: VecInt,
 }
 
 impl VecIntRange {
-    fn new(start: Vec<u8>, stop: Vec<u8>) -> VecIntRange {
-        VecIntRange { start: start.into(), stop: stop.into() }
+    fn new(start: VecInt, stop: VecInt) -> VecIntRange {
+        VecIntRange { start, stop }
+    }
+}
+
+impl Iterator for VecIntRange {
+    type Item = VecInt;
+
+    fn next(&mut self) -> Option<VecInt> {
+        if self.start > self.stop {
+            return None;
+        }
+        let v = self.start.clone();
+        self.start.increment();
+        Some(v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.start > self.stop {
+            return (0, Some(0));
+        }
+        // stop - start + 1 digit by digit, so endpoints of any length work
+        let n = VecInt { buf: digits::to_ascii(&self.stop.minus(&self.start)) };
+        match u64::try_from(&n).ok().and_then(|n| usize::try_from(n).ok()?.checked_add(1)) {
+            Some(n) => (n, Some(n)),
+            // there really are more than usize::MAX left
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+impl DoubleEndedIterator for VecIntRange {
+    fn next_back(&mut self) -> Option<VecInt> {
+        if self.start > self.stop {
+            return None;
+        }
+        let v = self.stop.clone();
+        if !self.stop.decrement() {
+            // stop was 0 so start was too, nothing is left
+            self.start.increment();
+        }
+        Some(v)
     }
 }
 
@@ -177,66 +314,138 @@ impl VecIntRange {
 // strings n % 2 == 1 are always valid because you cannot repeat a pattern TWO TIMES
 // in an odd-length string
 
-pub fn run() {
-    let mut reader = io::BufReader::new(fs::File::open("gift_shop.txt").expect("reading file failed"));
-    let mut buf = Vec::<u8>::new();
-    let _ = reader.read_until(b'\n', &mut buf);
-
-    let mut capfirst = true;
-    let mut start = Vec::<u8>::new();
-    let mut stop = Vec::<u8>::new();
-    let mut inventory = Vec::<VecIntRange>::new();
-
-    for c in buf.drain(..) {
-        //println!("Read char {}", c);
-        match c {
-            b'-' => {
-                capfirst = false;
-                //println!("Read record separator");
-            }
-            b',' | b'\0' | b'\n' => {
-                capfirst = true;
-                //println!("Read range separator or EOF");
-                let vir = VecIntRange::new(start.clone(), stop.clone());
-                inventory.push(vir.clone());
-                //println!("Constructed new range {}-{}",
-                //    Into::<u64>::into(vir.start),
-                //    Into::<u64>::into(vir.stop)
-                //);
-                start.clear();
-                stop.clear();
-            }
-            _ => {
-                if capfirst {
-                    start.push(c);
-                } else {
-                    stop.push(c);
+// Sum of the numbers in lo..=hi whose digits in `base` are a block
+// repeated, without visiting them. qualifies(len, period) picks which
+// digit lengths and smallest repeating periods count, a number like 222222
+// has period 1 and is only counted once however many blocks build it. A
+// number is k copies of a block whenever its period divides len / k.
+fn sum_periodic(lo: u64, hi: u64, base: u64, qualifies: impl Fn(usize, usize) -> bool) -> Answer {
+    let mut total = Answer::zero();
+    let b = base as u128;
+    for len in digits::to_digits(lo, base as u32).len()..=digits::to_digits(hi, base as u32).len() {
+        let lo = (lo as u128).max(b.pow(len as u32 - 1));
+        let hi = (hi as u128).min(b.pow(len as u32) - 1);
+        // the sums by smallest period, every divisor of p comes before p
+        let mut exact: Vec<(usize, Answer)> = Vec::new();
+        for p in (1..=len).filter(|p| len.is_multiple_of(*p)) {
+            let mut s = block_sum(lo, hi, b, len, p);
+            for (q, e) in &exact {
+                if p.is_multiple_of(*q) {
+                    s = s.checked_sub(e).unwrap();
                 }
             }
-        };
+            if qualifies(len, p) {
+                total += &s;
+            }
+            exact.push((p, s));
+        }
     }
+    total
+}
 
-    let mut ans: u64 = 0;
+// Sum of the len digit numbers in lo..=hi made of a p digit block. Those
+// are block * 1010..01, so it is an arithmetic series over the blocks.
+fn block_sum(lo: u128, hi: u128, base: u128, len: usize, p: usize) -> Answer {
+    let m = (base.pow(len as u32) - 1) / (base.pow(p as u32) - 1);
+    let first = lo.div_ceil(m).max(base.pow(p as u32 - 1));
+    let last = (hi / m).min(base.pow(p as u32) - 1);
+    if first > last {
+        return Answer::zero();
+    }
+    // halve whichever is even so nothing is lost
+    let (n, s) = (last - first + 1, first + last);
+    let (x, y) = if n.is_multiple_of(2) { (n / 2, s) } else { (n, s / 2) };
+    Answer::from(m) * Answer::from(x) * Answer::from(y)
+}
 
-    for mut ir in inventory {
-        //println!("Working on {:?}", ir.start);
-        let mut start = <VecInt as Into<u64>>::into(ir.start.clone()); 
-        let stop = <VecInt as Into<u64>>::into(ir.stop.clone()); 
-        //println!("Start: {} Stop: {}", start, stop);
-        while start <= stop {
-            match ir.start.invalid_two() {
-                true => {
-                    println!("{} is invalid", start);
-                    ans += start;
-                }
-                false => (),
+// A palindrome is fixed by its first half, so walk the halves between
+// those of lo and hi for each length. This visits every palindrome in the
+// range, which is about the square root of its width.
+fn sum_palindromes(lo: u64, hi: u64, base: u32) -> Answer {
+    let mut total = Answer::zero();
+    let (lo_d, hi_d) = (digits::to_digits(lo, base), digits::to_digits(hi, base));
+    for len in lo_d.len()..=hi_d.len() {
+        let half = len.div_ceil(2);
+        let first = match len == lo_d.len() {
+            true => digits::from_digits(&lo_d[..half], base).unwrap(),
+            false => (base as u64).pow(half as u32 - 1),
+        };
+        let last = match len == hi_d.len() {
+            true => digits::from_digits(&hi_d[..half], base).unwrap(),
+            false => (base as u64).pow(half as u32) - 1,
+        };
+        for h in first..=last {
+            let mut d = digits::to_digits(h, base);
+            d.extend(d[..len / 2].to_vec().iter().rev());
+            // from_digits can only overflow above hi
+            if let Some(x) = digits::from_digits(&d, base)
+                && (lo..=hi).contains(&x)
+            {
+                total += x;
             }
-            ir.start.increment();
-            start = <VecInt as Into<u64>>::into(ir.start.clone()); 
         }
     }
+    total
+}
+
+// 11-22,95-115,998-1012 spread over any number of lines. Whitespace and
+// line breaks around the numbers are ignored, and either works as well as
+// a comma between ranges.
+fn parse_inventory(src: &str) -> PResult<Vec<VecIntRange>> {
+    let ws = |c: &mut Cursor| c.take_while(char::is_whitespace).len();
+    let mut c = Cursor::new(src);
+    let mut ranges = Vec::new();
+    ws(&mut c);
+    while !c.is_eof() {
+        let at = c.clone();
+        // ids must fit in a u64 for the sums
+        let start = uint::<u64>()(&mut c)?;
+        ws(&mut c);
+        tag("-")(&mut c)?;
+        ws(&mut c);
+        let stop = uint::<u64>()(&mut c)?;
+        let r = VecIntRange::new(start.into(), stop.into());
+        if r.start > r.stop {
+            return Err(at.error(format!("a start no bigger than its stop {}", stop)));
+        }
+        ranges.push(r);
+
+        let from = c.pos();
+        ws(&mut c);
+        opt(tag(","))(&mut c)?;
+        ws(&mut c);
+        if c.pos() == from && !c.is_eof() {
+            return Err(c.error("a comma or space between ranges"));
+        }
+    }
+    Ok(ranges)
+}
 
-    println!("The password is {}", ans);
+fn merged(ranges: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let set: IntervalSet<u64> = ranges.iter().map(|&(a, b)| a..=b).collect();
+    set.iter().map(|r| r.into_inner()).collect()
+}
+
+// Without a rule both puzzle parts are run. With merge an id in more than
+// one range is only counted once.
+pub fn run(rule: Option<&InvalidRule>, base: u32, merge: bool) {
+    let src = fs::read_to_string("gift_shop.txt").expect("reading file failed");
+    let inventory = parse_inventory(&src).unwrap_or_else(|e| panic!("gift_shop.txt: {}", e));
+
+    // parse_inventory checked they fit
+    let id = |v: &VecInt| u64::try_from(v).unwrap();
+    let mut ranges: Vec<(u64, u64)> = inventory.iter().map(|ir| (id(&ir.start), id(&ir.stop))).collect();
+    if merge {
+        ranges = merged(&ranges);
+    }
+    let rules = match rule {
+        Some(r) => vec![r.clone()],
+        None => vec![InvalidRule::Repeat(2), InvalidRule::RepeatAtLeast(2)],
+    };
+    for rule in rules {
+        let ans: Answer = ranges.iter().map(|&(start, stop)| rule.sum(start, stop, base)).sum();
+        println!("The password for {} in base {} is {}", rule, base, ans);
+    }
     //assert_eq!(ans, 4174379265);
     // 73694270733 -- too high
     // 4174379265 -- sample
@@ -247,4 +456,195 @@ pub fn run() {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn test_parse_inventory() {
+        let inv = parse_inventory("11-22,95-115\n").unwrap();
+        assert_eq!(inv.len(), 2);
+        assert_eq!(inv[1].start, VecInt::from(95u64));
+        assert_eq!(inv[1].stop, VecInt::from(115u64));
+        let e = parse_inventory("11-22,95115").unwrap_err();
+        assert_eq!(e.col, 12);
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let inv = parse_inventory("  11-22,\n95 - 115\n\n998-1012,\n").unwrap();
+        let ids: Vec<(u64, u64)> =
+            inv.iter().map(|r| (u64::try_from(&r.start).unwrap(), u64::try_from(&r.stop).unwrap())).collect();
+        assert_eq!(ids, vec![(11, 22), (95, 115), (998, 1012)]);
+        assert!(parse_inventory("").unwrap().is_empty());
+
+        // reversed, the error points at the start
+        let e = parse_inventory("11-22,\n  115-95").unwrap_err();
+        assert_eq!((e.line, e.col), (2, 3));
+        assert!(e.expected.contains("95"));
+        let e = parse_inventory("11-22x3").unwrap_err();
+        assert_eq!((e.line, e.col), (1, 6));
+        let e = parse_inventory("11-22,-5").unwrap_err();
+        assert_eq!((e.line, e.col), (1, 7));
+        assert_eq!(parse_inventory("11-22 33-44").unwrap().len(), 2);
+        assert_eq!(parse_inventory("11-22,,33-44").unwrap_err().col, 7);
+        // one past u64::MAX on the second line
+        let e = parse_inventory("11-22\n1-18446744073709551616").unwrap_err();
+        assert_eq!((e.line, e.col), (2, 3));
+        assert!(e.expected.contains("u64"));
+    }
+
+    #[test]
+    fn test_merge() {
+        // 22 is in both
+        let ranges = [(10, 30), (20, 40), (41, 50)];
+        assert_eq!(merged(&ranges), vec![(10, 50)]);
+        let sum = |ranges: &[(u64, u64)]| ranges.iter().map(|&(a, b)| InvalidRule::Repeat(2).sum(a, b, 10)).sum::<Answer>();
+        assert_eq!(sum(&ranges), Answer::from(11u64 + 22 + 22 + 33 + 44));
+        assert_eq!(sum(&merged(&ranges)), Answer::from(11u64 + 22 + 33 + 44));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (one, two) = (InvalidRule::Repeat(2), InvalidRule::RepeatAtLeast(2));
+        let mut v = VecInt::from(99u64);
+        assert!(v.is_invalid(&one));
+        assert!(v.is_invalid(&two));
+        v.increment();
+        assert_eq!(v, VecInt::from(100u64));
+        assert!(!v.is_invalid(&two));
+        assert!(VecInt::from(111u64).is_invalid(&two));
+        assert!(!VecInt::from(111u64).is_invalid(&one));
+        assert!(VecInt::from(121u64).is_invalid(&InvalidRule::Palindrome));
+        assert!(!VecInt::from(1212u64).is_invalid(&InvalidRule::Palindrome));
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(VecInt::try_from("1012"), Ok(VecInt::from(1012u64)));
+        assert_eq!(VecInt::try_from(&b"007"[..]), Ok(VecInt::from(7u64)));
+        assert_eq!(VecInt::try_from(b"000".to_vec()), Ok(VecInt::from(0u64)));
+        assert_eq!(VecInt::try_from(""), Err(VecIntError::Empty));
+        assert_eq!(VecInt::try_from("12a4"), Err(VecIntError::NotADigit { at: 2, found: b'a' }));
+        assert_eq!(VecInt::try_from("95\0"), Err(VecIntError::NotADigit { at: 2, found: 0 }));
+
+        let max = VecInt::from(u64::MAX);
+        assert_eq!(u64::try_from(&max), Ok(u64::MAX));
+        let mut past = max.clone();
+        past.increment();
+        assert_eq!(u64::try_from(&past), Err(VecIntError::Overflow));
+        assert_eq!(u128::try_from(&past), Ok(u64::MAX as u128 + 1));
+        assert_eq!(VecInt::from(u128::MAX), VecInt::try_from(u128::MAX.to_string().as_str()).unwrap());
+        assert_eq!(u128::try_from(&VecInt::from(u128::MAX)), Ok(u128::MAX));
+        assert_eq!(u128::try_from(&VecInt::try_from("9".repeat(40).as_str()).unwrap()), Err(VecIntError::Overflow));
+
+        assert!(VecInt::from(100u64) > VecInt::from(99u64));
+        assert!(VecInt::from(12u64) < VecInt::from(21u64));
+    }
+
+    #[test]
+    fn test_range_iterator() {
+        let r = VecIntRange::new(VecInt::from(95u64), VecInt::from(101u64));
+        assert_eq!(r.size_hint(), (7, Some(7)));
+        let ids: Vec<u64> = r.clone().map(|v| u64::try_from(&v).unwrap()).collect();
+        assert_eq!(ids, (95..=101).collect::<Vec<_>>());
+        let back: Vec<u64> = r.clone().rev().map(|v| u64::try_from(&v).unwrap()).collect();
+        assert_eq!(back, (95..=101).rev().collect::<Vec<_>>());
+
+        // both ends meet in the middle
+        let mut r = r;
+        assert_eq!(r.next(), Some(VecInt::from(95u64)));
+        assert_eq!(r.next_back(), Some(VecInt::from(101u64)));
+        assert_eq!(r.size_hint(), (5, Some(5)));
+        assert_eq!(r.count(), 5);
+
+        let mut zero = VecIntRange::new(VecInt::from(0u64), VecInt::from(0u64));
+        assert_eq!(zero.next_back(), Some(VecInt::from(0u64)));
+        assert_eq!(zero.next_back(), None);
+        assert_eq!(zero.next(), None);
+        assert_eq!(VecIntRange::new(VecInt::from(5u64), VecInt::from(4u64)).size_hint(), (0, Some(0)));
+
+        let huge = VecIntRange::new(VecInt::from(0u64), VecInt::from(u128::MAX));
+        assert_eq!(huge.size_hint(), (usize::MAX, None));
+        // endpoints past u128 but only a few apart
+        let big = |s: &str| VecInt::try_from(s).unwrap();
+        let one = VecIntRange::new(big(&"9".repeat(45)), big(&"9".repeat(45)));
+        assert_eq!(one.size_hint(), (1, Some(1)));
+        assert_eq!(one.count(), 1);
+        let r = VecIntRange::new(big(&format!("{}98", "9".repeat(43))), big(&format!("1{}03", "0".repeat(43))));
+        assert_eq!(r.size_hint(), (6, Some(6)));
+        assert_eq!(r.count(), 6);
+        let sum: u64 = VecIntRange::new(VecInt::from(11u64), VecInt::from(22u64))
+            .filter(|v| v.is_invalid(&InvalidRule::Repeat(2)))
+            .map(|v| u64::try_from(&v).unwrap())
+            .sum();
+        assert_eq!(sum, 33);
+    }
+
+    #[test]
+    fn test_rules() {
+        // every rule against checking every id, in a couple of bases
+        let rules = [
+            InvalidRule::Repeat(2),
+            InvalidRule::Repeat(3),
+            InvalidRule::RepeatAtLeast(2),
+            InvalidRule::RepeatAtLeast(3),
+            InvalidRule::Palindrome,
+        ];
+        for base in [2, 10, 16] {
+            for rule in &rules {
+                for (a, b) in [(1, 5000), (95, 115), (4097, 70000), (7, 7)] {
+                    let brute: u64 = (a..=b).filter(|&x| rule.matches(&digits::to_digits(x, base))).sum();
+                    assert_eq!(rule.sum(a, b, base), Answer::from(brute), "{} base {} {}-{}", rule, base, a, b);
+                }
+            }
+        }
+        // 5 = 101 and 7 = 111 in binary
+        assert_eq!(InvalidRule::Palindrome.sum(4, 7, 2), Answer::from(12u64));
+        let odd = InvalidRule::Custom(|d| d.last().is_some_and(|x| x % 2 == 1));
+        assert_eq!(odd.sum(1, 10, 10), Answer::from(25u64));
+        assert_eq!(InvalidRule::Palindrome.sum(u64::MAX - 1000, u64::MAX, 10), Answer::zero());
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        assert!(matches!("repeat:2".parse(), Ok(InvalidRule::Repeat(2))));
+        assert!(matches!("repeat-at-least:3".parse(), Ok(InvalidRule::RepeatAtLeast(3))));
+        assert!(matches!("palindrome".parse(), Ok(InvalidRule::Palindrome)));
+        assert!("repeat:0".parse::<InvalidRule>().is_err());
+        assert!("repeat".parse::<InvalidRule>().is_err());
+        assert!("twice:2".parse::<InvalidRule>().is_err());
+        assert_eq!(InvalidRule::RepeatAtLeast(2).to_string(), "repeat-at-least:2");
+    }
+
+    #[test]
+    fn test_sum_periodic() {
+        let sample = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,\
+                      446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
+        let ranges: Vec<(u64, u64)> = sample
+            .split(',')
+            .map(|r| {
+                let (a, b) = r.split_once('-').unwrap();
+                (a.parse().unwrap(), b.parse().unwrap())
+            })
+            .collect();
+        let sum = |qualifies: fn(usize, usize) -> bool| {
+            ranges.iter().map(|&(a, b)| sum_periodic(a, b, 10, qualifies)).sum::<Answer>()
+        };
+        // 222222 is two halves as well as six 2s
+        assert_eq!(sum(|len, p| len.is_multiple_of(2) && (len / 2).is_multiple_of(p)), Answer::from(1227775554u64));
+        assert_eq!(sum(|len, p| len / p >= 2), Answer::from(4174379265u64));
+
+        // against checking every id, 222222 is only counted once
+        let brute = |a: u64, b: u64| {
+            (a..=b).filter(|&x| VecInt::from(x).is_invalid(&InvalidRule::RepeatAtLeast(2))).sum::<u64>()
+        };
+        for (a, b) in [(1, 100_000), (222_000, 223_000), (999_990, 1_000_010), (5, 5)] {
+            assert_eq!(sum_periodic(a, b, 10, |len, p| len / p >= 2), Answer::from(brute(a, b)), "{}-{}", a, b);
+        }
+
+        // the whole of u64 is no slower, 11 is the smallest and
+        // 99999999999 the largest 11 digit one
+        assert_eq!(sum_periodic(1, 11, 10, |len, p| len / p >= 2), Answer::from(11u64));
+        let all = sum_periodic(1, u64::MAX, 10, |len, p| len / p >= 2);
+        assert!(all > Answer::from(u64::MAX));
+        assert_eq!(sum_periodic(0b101, 0b111, 2, |len, p| len / p >= 2), Answer::from(7u64));
+    }
 }
@@ -1,11 +1,14 @@
 use std::fs;
 use std::io::{self, BufRead};
 
+use crate::answer::Answer;
+use crate::digits;
+
 #[derive(Debug, Clone)]
 struct Battery {
     cells: Vec<u8>,
     //jolts: u8,
-    jolts: u64,
+    jolts: Answer,
     tested: bool,
 }
 
@@ -13,7 +16,7 @@ impl From<Vec<u8>> for Battery {
     fn from(item: Vec<u8>) -> Battery {
         Battery {
             cells: item,
-            jolts: 0,
+            jolts: Answer::zero(),
             tested: false,
         }
     }
@@ -56,31 +59,13 @@ impl Battery {
     //    self.tested = true;
     //    return self.jolts;
     //}
-    fn test(&mut self) -> u64 {
-        //need to loop and find the largest number after the last position of the largest number of each loop
-        //    so the max search space shrinks with each digit
-
-        let mut jolts = Vec::<u8>::new();
-        let mut idx: usize = 0;
-        let mut ix: usize = 0;
-        let mut huge: u8 = 48; // code for b'0'
-        // we find the 12 largest cells
-        for i in 0..12 {
-            // we don't search to the end because we need room for the following cells
-            for j in idx+ix..self.cells.len()-(11-i) {
-                if self.cells[j] > huge {
-                    huge = self.cells[j];
-                    idx = j;
-                }
-            }
-            jolts.push(huge);
-            huge = 48;
-            ix = 1;
-        }
+    fn test(&mut self) -> Answer {
+        // the 12 cells that read as the biggest number, in order
+        let jolts = digits::largest_subsequence(&self.cells, 12);
         println!("{:?}", jolts);
-        self.jolts = String::from_utf8(jolts).unwrap().parse::<u64>().unwrap();
+        self.jolts = String::from_utf8(jolts).unwrap().parse::<Answer>().unwrap();
         self.tested = true;
-        return self.jolts;
+        return self.jolts.clone();
     }
 }
 
@@ -104,12 +89,12 @@ pub fn run() {
         }
     }
 
-    let mut jolts: u64 = 0;
+    let mut jolts = Answer::zero();
 
     for mut b in inventory {
         let j = b.test();
         println!("{:?}", b);
-        jolts += j;
+        jolts += &j;
     }
     println!("Bank capacity: {}", jolts);
     // 171419245422055
@@ -119,4 +104,14 @@ pub fn run() {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn test_battery() {
+        let mut b = Battery::from(b"987654321111111".to_vec());
+        assert_eq!(b.test(), Answer::from(987654321111u64));
+        let mut b = Battery::from(b"811111111111119".to_vec());
+        assert_eq!(b.test(), Answer::from(811111111119u64));
+        let mut b = Battery::from(b"818181911112111".to_vec());
+        assert_eq!(b.test(), Answer::from(888911112111u64));
+    }
 }
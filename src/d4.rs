@@ -1,8 +1,23 @@
 use std::fs;
 use std::io::{self, BufRead};
+use std::path::Path;
 
-#[allow(unused_assignments)]
-pub fn run() {
+use crate::automaton::{Automaton, Neighbourhood, Update};
+use crate::export::{self, Frames, Rgb};
+
+// 46 == b'.' empty
+// 64 == b'@' paper
+
+fn floor_color(c: &u8) -> Rgb {
+    if *c == 64 { export::WHITE } else { export::BLACK }
+}
+
+// a roll with fewer than four rolls around it can be reached by a forklift
+fn forklift(c: u8, around: &[u8]) -> u8 {
+    if c == 64 && around.iter().filter(|&&n| n == 64).count() < 4 { 46 } else { c }
+}
+
+pub fn run(export: Option<&Path>) {
     let mut reader = io::BufReader::new(fs::File::open("forklift.txt").expect("reading file failed"));
     let mut line = String::new();
 
@@ -22,65 +37,34 @@ pub fn run() {
         }
     }
 
-    //println!("{:?}", floor);
-    //for cell in floor, calculate the 8 adjacent positions (may be fewer than 8 if on an edge
-    //check the valid cells for paper
-    //count the number of paper
-    //return whether that cell can be accessed
-    //
-    // 46 == b'.' empty
-    // 64 == b'@' paper
-
-    let mut total_paper = 0;
-
-    fn check(floor: &Vec<Vec<u8>>, x: usize, y: usize) -> bool {
-        let mut paper = 0;
-
-        let ys = if y == 0 { y } else { y - 1 };
-        let ye = if y == floor.len()-1 { y } else { y + 1 };
-        let xs = if x == 0 { x } else { x - 1 };
-        let xe = if x == floor[y].len()-1 { x } else { x + 1 };
-
-        //println!("Searching the area {xs}, {ys} - {xe}, {ye} for location {x}, {y}");
-
-        for j in ys..=ye {
-            for i in xs..=xe {
-                if i == x && j == y {
-                    //println!("Skipping {}", floor[j][i]);
-                    continue;
-                }
-                //println!("Checking {}", floor[j][i]);
-                if floor[j][i] == 64 {
-                    paper += 1;
-                }
-            }
-        }
-        return paper < 4;
+    // sweep the floor in place, each sweep can free up rolls for the next
+    let mut floor = Automaton::new(floor, Neighbourhood::Moore, Update::InPlace);
+    if let Some(p) = export {
+        floor.record(Frames::new(p), 4, floor_color);
+    }
+    let stats = floor.run(forklift, None);
+    for s in &stats.steps {
+        println!("Removed {} rolls in sweep {}", s.changed, s.step);
     }
+    println!("Cannot remove more rolls");
+    println!("Finished: Removed {} rolls", stats.changed());
+}
 
-    loop {
-        let mut paper = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        for y in 0..floor.len() {
-            for x in 0..floor[y].len() {
-                // don't check if it's not paper
-                if floor[y][x] == 64 {
-                    if check(&floor, x, y) {
-                        println!("Removing paper at {}, {}", x, y);
-                        floor[y][x] = 46;
-                        paper += 1;
-                    }
-                }
-            }
-        }
+    const SAMPLE: &str = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n.@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.\n";
 
-        println!("Removed {} rolls", paper);
-        if paper == 0 {
-            println!("Cannot remove more rolls");
-            break;
-        }
-        total_paper += paper;
-        paper = 0;
+    #[test]
+    fn test_forklift() {
+        // everything reachable on the untouched floor
+        let mut floor = Automaton::parse(SAMPLE, |c| c as u8, Neighbourhood::Moore, Update::Synchronous);
+        assert_eq!(floor.step(forklift).changed, 13);
+        // sweeping order doesn't change where it ends up
+        let mut floor = Automaton::parse(SAMPLE, |c| c as u8, Neighbourhood::Moore, Update::InPlace);
+        let stats = floor.run(forklift, None);
+        assert!(stats.fixed_point);
+        assert_eq!(stats.changed(), 43);
     }
-    println!("Finished: Removed {} rolls", total_paper);
 }
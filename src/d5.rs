@@ -1,53 +1,16 @@
 use std::fs;
 use std::io::{self, BufRead};
 
-#[derive(Debug)]
-struct FreshIngredientRange {
-    start: u64,
-    stop: u64,
-}
-
-impl FreshIngredientRange {
-    fn new(start: u64, stop: u64) -> Self {
-        Self { start, stop }
-    }
-
-    fn is_fresh(&self, id: u64) -> bool {
-        id >= self.start && id <= self.stop
-    }
+use crate::interval::IntervalSet;
 
-    fn num_fresh(&self) -> u64 {
-        // +1 because inclusive range
-        self.stop - self.start + 1
-    }
-
-    #[allow(unused_assignments)]
-    fn union(&self, rhs: &Self) -> Self {
-        let mut cs: u64 = 0;
-        let mut ce: u64 = 0;
-        if self.start <= rhs.start {
-            cs = self.start;
-        } else {
-            cs = rhs.start;
-        }
-        if self.stop >= rhs.stop {
-            ce = self.stop;
-        } else {
-            ce = rhs.stop;
-        }
-        Self { start: cs, stop: ce }
-    }
-
-    fn overlaps(&self, rhs: &Self) -> bool {
-        (self.start <= rhs.stop && self.start >= rhs.start) || (self.stop >= rhs.start && self.stop <= rhs.stop) || (rhs.start <= self.stop && rhs.start >= self.start) || (rhs.stop >= self.start && rhs.stop <= self.stop)
-    }
-}
+// every fresh id, overlapping ranges from the file are merged on insert
+type FreshIngredients = IntervalSet<u64>;
 
 pub fn run() {
     let mut reader = io::BufReader::new(fs::File::open("cafe.txt").expect("reading file failed"));
     let mut line = String::new();
 
-    let mut ranges = Vec::<FreshIngredientRange>::new();
+    let mut ranges = FreshIngredients::new();
     let mut parse_ranges = true;
     let mut ings = Vec::<u64>::new();
 
@@ -76,7 +39,7 @@ pub fn run() {
                     //    // no ranges are unordered
                     //    panic!("unordered range");
                     //}
-                    ranges.push(FreshIngredientRange::new(fi, li));
+                    ranges.insert(fi..=li);
                 } else {
                     //println!("Adding ingredient {}", line);
                     ings.push(line.parse::<u64>().expect("Failed to parse u64 from line"));
@@ -87,119 +50,65 @@ pub fn run() {
         }
     }
 
-    // union the ranges until there are no more overlaps
-    loop {
-        let mut done = true;
-        for i in 0..ranges.len() {
-            for j in 0..ranges.len() {
-                if i == j {
-                    continue;
-                }
-                if ranges[i].overlaps(&ranges[j]) {
-                    done = false;
-                    let a = ranges.remove(i);
-                    // -1 because we change the length with remove
-                    // we can then safely use swap_remove because we only maintain order
-                    // on the first remove
-                    let b = ranges.swap_remove(j-1);
-                    let c = a.union(&b);
-                    println!("Union {}-{} from {}-{} and {}-{}", c.start, c.stop, a.start, a.stop, b.start, b.stop);
-                    ranges.push(c);
-                    // break inner loop if we change the size
-                    break;
-                }
-            }
-            if !done {
-                // break outer loop if we change the size
-                break;
-            }
-        }
-        if done {
-            break;
-        }
-    }
-
-    for i in &ranges {
+    for i in ranges.iter() {
         println!("{:?}", i);
     }
 
     let mut fresh = Vec::<u64>::new();
 
     for i in ings {
-        for r in &ranges {
-            if r.is_fresh(i) {
-                //println!("{} is fresh", i);
-                fresh.push(i);
-                break;
-            }
+        if ranges.contains(i) {
+            //println!("{} is fresh", i);
+            fresh.push(i);
         }
     }
 
     println!("There are {} fresh ing", fresh.len());
     assert_eq!(fresh.len(), 707);
     //too high - 477207365413044
-    println!("There are {} fresh ids", ranges.into_iter().map(|x| x.num_fresh()).sum::<u64>());
+    println!("There are {} fresh ids", ranges.covered());
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn ranges(v: &[(u64, u64)]) -> Vec<(u64, u64)> {
+        let r: FreshIngredients = v.iter().map(|&(a, b)| a..=b).collect();
+        r.iter().map(|r| r.into_inner()).collect()
+    }
+
     #[test]
     fn test_overlap() {
-        let a = FreshIngredientRange::new(0, 1);
-        let b = FreshIngredientRange::new(1, 2);
-        assert_eq!(a.overlaps(&b), true);
+        assert_eq!(ranges(&[(0, 1), (1, 2)]), vec![(0, 2)]);
     }
 
     #[test]
     fn test_no_overlap() {
-        let a = FreshIngredientRange::new(0, 0);
-        let b = FreshIngredientRange::new(1, 1);
-        assert_eq!(a.overlaps(&b), false);
+        assert_eq!(ranges(&[(0, 0), (2, 2)]), vec![(0, 0), (2, 2)]);
     }
 
     #[test]
     fn test_fresh_ing() {
-        let a = FreshIngredientRange::new(0, 0);
-        assert_eq!(a.num_fresh(), 1);
+        let r: FreshIngredients = [0..=0].into_iter().collect();
+        assert_eq!(r.covered(), 1);
+        assert!(r.contains(0));
+        assert!(!r.contains(1));
     }
 
     #[test]
     fn test_big_overlaps_small() {
-        let a = FreshIngredientRange::new(0, 3);
-        let b = FreshIngredientRange::new(1, 2);
-        assert_eq!(a.overlaps(&b), true);
+        assert_eq!(ranges(&[(0, 3), (1, 2)]), vec![(0, 3)]);
     }
 
     #[test]
     fn test_small_overlaps_big() {
-        let a = FreshIngredientRange::new(0, 3);
-        let b = FreshIngredientRange::new(1, 2);
-        assert_eq!(b.overlaps(&a), true);
-    }
-
-    #[test]
-    fn test_enclosed_union() {
-        let a = FreshIngredientRange::new(0, 3);
-        let b = FreshIngredientRange::new(1, 2);
-        let c = a.union(&b);
-        let d = b.union(&a);
-        assert_eq!(c.start, 0);
-        assert_eq!(c.stop, 3);
-        assert_eq!(d.start, 0);
-        assert_eq!(d.stop, 3);
+        assert_eq!(ranges(&[(1, 2), (0, 3)]), vec![(0, 3)]);
     }
 
     #[test]
-    fn test_union() {
-        let a = FreshIngredientRange::new(0, 1);
-        let b = FreshIngredientRange::new(1, 2);
-        let c = a.union(&b);
-        let d = b.union(&a);
-        assert_eq!(c.start, 0);
-        assert_eq!(c.stop, 2);
-        assert_eq!(d.start, 0);
-        assert_eq!(d.stop, 2);
+    fn test_chained_union() {
+        // one range bridging two others that were apart
+        assert_eq!(ranges(&[(0, 1), (5, 6), (1, 5)]), vec![(0, 6)]);
     }
 }
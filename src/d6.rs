@@ -1,33 +1,25 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-
-#[derive(Debug, PartialEq, Eq)]
-enum Parser {
-    DIGIT,
-    OPCODE,
-    SPACE,
-    RESET,
-}
+use std::fs;
+
+use crate::answer::Answer;
+use crate::parse::{self, Cursor, PResult, ParseError};
 
 #[derive(Debug)]
 enum ColumnOp {
     ADD,
     MUL,
-    NOOP,
 }
 
 #[derive(Debug)]
 struct Column {
     intcol: Vec<Vec<char>>,
-    ints: Vec<u64>,
+    ints: Vec<Answer>,
     op: ColumnOp,
-    width: usize, 
 }
 
 impl Column {
     fn new(op: ColumnOp, width: usize) -> Self {
-        let ic = vec![Vec::<char>::new(); 4];
-        Self { intcol: ic, ints: Vec::<u64>::new(), op, width }
+        let ic = vec![Vec::<char>::new(); width];
+        Self { intcol: ic, ints: Vec::<Answer>::new(), op }
     }
 
     fn _parse_cols(&mut self) {
@@ -35,141 +27,83 @@ impl Column {
             let s = String::from_iter(c.clone());
             println!("Got string {}", s);
             if s != "" {
-                self.ints.push(s.parse::<u64>().unwrap());
+                self.ints.push(s.parse::<Answer>().unwrap());
             }
         }
     }
 
-    fn calculate(&mut self) -> u128 {
+    fn calculate(&mut self) -> Answer {
         self._parse_cols();
         match self.op {
             ColumnOp::ADD => {
-                return self.ints.clone().into_iter().sum::<u64>().into();
+                return self.ints.clone().into_iter().sum();
             }
             ColumnOp::MUL => {
-                let mut ans: u128 = self.ints[0].into();
-                for i in 1..self.ints.len() {
-                    ans = ans * Into::<u128>::into(self.ints[i]);
-                }
-                return ans;
-            }
-            ColumnOp::NOOP => {
-                panic!("{:?} specified NOOP", self);
+                return self.ints.clone().into_iter().product();
             }
         }
     }
 
 }
 
-#[allow(unused_variables, unused_assignments)]
-fn do_opline(columns: &mut Vec<Column>, line: &String) {
-    let mut op = ColumnOp::NOOP;
-    let mut width: usize = 0;
-    let mut state = Parser::RESET;
-
-    for c in line.chars() {
-        match c {
-            '*' | '+' => {
-                if state == Parser::SPACE {
-                    columns.push(Column::new(op, width));
-                    width = 0;
-                }
-                op = if c == '*' { ColumnOp::MUL } else { ColumnOp::ADD };
-                state = Parser::OPCODE;
-                width += 1;
-            }
-            ' ' => {
-                state = Parser::SPACE;
-                width += 1;
-            }
-            _ => {}
-        }
-    }
-    // append the last column
-    columns.push(Column::new(op, width));
-    //println!("{:?}", columns);
-}
+fn parse_columns(src: &str) -> PResult<Vec<Column>> {
+    // the opcode always is in line with the msb of the column
+    // there is always a full vertical space between columns
+    // the column width can vary from 1-4
+    let lines: Vec<&str> = src.trim_end_matches('\n').lines().collect();
+    let (opline, numlines) = match lines.split_last() {
+        Some(x) => x,
+        None => return Err(Cursor::new(src).error("a line of numbers")),
+    };
+    let oplineno = lines.len();
 
-#[allow(unused_variables, unused_assignments)]
-fn do_line(columns: &mut Vec<Column>, line: &String) {
-    let mut idx: usize = 0;
-    let bint = Vec::<char>::new();
-    let mut state = Parser::RESET;
-
-    // we know our puzzle only has four lines so we could probably cheese this
-    // instead of writing a true custom format parser
-    for c in columns {
-        // read width from line
-        for (n, i) in line[idx..idx+c.width].chars().enumerate() {
-            match i {
-                ' ' => {
-                    //println!("Found space");
-                    state = Parser::SPACE;
-                }
-                '0'..='9' => {
-                    //println!("Pushing digit {}", i);
-                    state = Parser::DIGIT;
-                    println!("PUSH {} to {}", i, n);
-                    c.intcol[n].push(i);
-                    //bint.push(i)
+    let mut columns = Vec::<Column>::new();
+
+    for r in parse::columns(&lines) {
+        let op = match parse::cut(opline, &r).trim() {
+            "*" => ColumnOp::MUL,
+            "+" => ColumnOp::ADD,
+            x => {
+                return Err(ParseError {
+                    line: oplineno,
+                    col: r.start + 1,
+                    expected: "an opcode".to_string(),
+                    found: format!("{:?}", x),
+                });
+            }
+        };
+        let mut col = Column::new(op, r.len());
+        for (lineno, l) in numlines.iter().enumerate() {
+            for (n, i) in parse::cut(l, &r).chars().enumerate() {
+                match i {
+                    ' ' => {}
+                    '0'..='9' => col.intcol[n].push(i),
+                    x => {
+                        return Err(ParseError {
+                            line: lineno + 1,
+                            col: r.start + n + 1,
+                            expected: "a digit".to_string(),
+                            found: format!("{:?}", x),
+                        });
+                    }
                 }
-                _ => {}
             }
         }
-        //let s = String::from_iter(bint.clone());
-        ////println!("Got string {}", s);
-        //c.ints.push(s.parse::<u64>().unwrap());
-        //bint.clear();
-        idx += c.width;
-        //println!("New idx {}", idx);
+        columns.push(col);
     }
-    //println!("{:?}", line);
+    return Ok(columns);
 }
 
 pub fn run() {
-    let mut reader = BufReader::new(File::open("trash.txt").expect("reading file failed"));
-
-    // the opcode always is in line with the msb of the column
-    // there is always a full vertical space
-    // the colum width can vary from 1-4
-    //
-
-    let mut columns = Vec::<Column>::new();
-    let mut lines = Vec::<String>::new();
-    let mut line = String::new();
-
-    loop {
-        match reader.read_line(&mut line) {
-            Ok(x) => {
-                if x == 0 {
-                    break;
-                }
-                // discard \n
-                let _ = line.pop();
-                if line.starts_with("*") || line.starts_with("+") {
-                    //println!("Parsing operations line");
-                    do_opline(&mut columns, &line);
-                } else {
-                    lines.push(line.clone());
-                    line.clear();
-                }
-            }
-            Err(x) => {
-                panic!("{:?}", x);
-            }
-        }
-    }
+    let src = fs::read_to_string("trash.txt").expect("reading file failed");
 
-    for l in &lines {
-        //println!("Processing line");
-        do_line(&mut columns, &l);
-    }
+    let columns = parse_columns(&src).unwrap_or_else(|e| panic!("trash.txt: {}", e));
 
     println!("{:?}", columns);
 
-    let mut ans: u128 = 0;
+    let mut ans = Answer::zero();
     for mut c in columns {
-        ans += c.calculate();
+        ans += &c.calculate();
     }
     println!("Ans {}", ans);
     // 4648618073226 = ans
@@ -181,4 +115,30 @@ pub fn run() {
 mod tests {
     use super::*;
 
+    const SAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+    #[test]
+    fn test_parse_columns() {
+        let columns = parse_columns(SAMPLE).unwrap();
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[3].intcol.len(), 3);
+        let ans: Answer = columns.into_iter().map(|mut c| c.calculate()).sum();
+        assert_eq!(ans, Answer::from(3263827u64));
+    }
+
+    #[test]
+    fn test_parse_columns_errors() {
+        let e = parse_columns("12 3\n*  -\n").unwrap_err();
+        assert_eq!((e.line, e.col), (2, 4));
+        let e = parse_columns("12 x\n*  +\n").unwrap_err();
+        assert_eq!((e.line, e.col), (1, 4));
+    }
+
+    #[test]
+    fn test_calculate_overflow() {
+        let mut c = Column::new(ColumnOp::MUL, 21);
+        c.intcol[0] = "99999999999".chars().collect();
+        c.intcol[1] = "99999999999".chars().collect();
+        assert_eq!(c.calculate().to_string(), "9999999999800000000001");
+    }
 }
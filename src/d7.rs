@@ -1,13 +1,41 @@
 use std::fs::File;
 use std::io::{BufReader, Read};
+use std::path::Path;
 
-pub fn run() {
+use crate::answer::Answer;
+use crate::export::{self, Image};
+
+fn beam_image(manifold: &[Vec<u8>], rows: &[Vec<Answer>]) -> Image {
+    // brighter cells carry more timelines, on a log scale because the
+    // counts grow exponentially down the manifold
+    let max = rows.iter().flatten().map(|a| a.to_f64()).fold(1.0, f64::max);
+    let mut cells = Vec::<Vec<export::Rgb>>::new();
+    for (y, line) in manifold.iter().enumerate() {
+        let mut row = Vec::<export::Rgb>::new();
+        for (x, &c) in line.iter().enumerate() {
+            let count = rows.get(y).and_then(|r| r.get(x)).map(|a| a.to_f64()).unwrap_or(0.0);
+            row.push(match c {
+                b'^' => export::RED,
+                b'S' => export::WHITE,
+                _ if count > 0.0 => export::ramp(export::GREEN, 0.2 + 0.8 * count.ln_1p() / max.ln_1p()),
+                _ => export::BLACK,
+            });
+        }
+        cells.push(row);
+    }
+    Image::from_grid(&cells, 4, |&c| c)
+}
+
+pub fn run(export: Option<&Path>) {
     let mut reader = BufReader::new(File::open("beam.txt").expect("reading file failed"));
     let mut buf = Vec::<u8>::new();
     let _ = reader.read_to_end(&mut buf);
+    let manifold: Vec<Vec<u8>> = buf.split(|&c| c == b'\n').filter(|l| !l.is_empty()).map(|l| l.to_vec()).collect();
+    // the timeline count of every cell, row by row
+    let mut rows = Vec::<Vec<Answer>>::new();
 
-    let mut lbuf = Vec::<u64>::new();
-    let mut cbuf = Vec::<u64>::new();
+    let mut lbuf = Vec::<Answer>::new();
+    let mut cbuf = Vec::<Answer>::new();
     let mut lchar: u8 = b'.';
 
     let mut reset: bool = false;
@@ -15,22 +43,21 @@ pub fn run() {
 
     for c in buf.drain(..) {
         if !(lchar == b'^') {
-            cbuf.push(0);
+            cbuf.push(Answer::zero());
         }
         match c {
             b'.' => {
                 if lchar == b'^' {
-                    cbuf.push(lbuf[idx-1]);
+                    cbuf.push(lbuf[idx-1].clone());
                     println!("idx {}", idx);
                     println!("las buf {}", lbuf[idx-1]);
-                    cbuf[idx] = lbuf[idx];
-                    cbuf[idx] += lbuf[idx-1];
+                    cbuf[idx] = &lbuf[idx] + &lbuf[idx-1];
                 } else {
                     if idx < lbuf.len() {
-                        cbuf[idx] = lbuf[idx];
+                        cbuf[idx] = lbuf[idx].clone();
                     } else {
                         // we grow the vec for the first row
-                        cbuf[idx] = 0;
+                        cbuf[idx] = Answer::zero();
                     }
                 }
             }
@@ -40,22 +67,23 @@ pub fn run() {
                 // handle setting cell right based on cell upper-right ?
                 println!("lbuf {:?}", lbuf);
                 println!("cbuf {:?}", cbuf);
-                cbuf[idx-1] += lbuf[idx];
+                cbuf[idx-1] += &lbuf[idx];
                 // beam split, current cell has zero paths
-                cbuf[idx] = 0;
+                cbuf[idx] = Answer::zero();
             }
             b'\n' => {
                 println!("Resetting line");
                 // swap buffers
                 // we don't do anything clever with pointers, we just clone
                 lbuf = cbuf.clone();
+                rows.push(lbuf.clone());
                 cbuf.clear();
                 reset = true;
             }
             b'S' => {
                 // set current index to 1
                 // there will only be one source
-                cbuf[idx] = 1;
+                cbuf[idx] = Answer::one();
             }
             _ => {
             }
@@ -71,5 +99,10 @@ pub fn run() {
 
     println!("{:?}", lbuf);
     println!("{:?}", cbuf);
-    println!("There are {} timelines", lbuf.into_iter().sum::<u64>());
+    println!("There are {} timelines", lbuf.into_iter().sum::<Answer>());
+
+    if let Some(p) = export {
+        beam_image(&manifold, &rows).write(p).expect("writing image failed");
+        println!("Wrote {}", p.display());
+    }
 }
@@ -1,9 +1,12 @@
-use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::ops::Sub;
 use std::fmt;
 use std::boxed::Box;
+use std::path::Path;
+
+use crate::export::Svg;
+use crate::memo::Memo;
 
 type Point = (i64, i64);
 
@@ -11,8 +14,7 @@ type Point = (i64, i64);
 struct Polygon {
     points: Vec<Point>,
     verticals: Vec<(Point, Point)>,
-    cache: HashMap<Point, bool>,
-    cache_hits: u64,
+    cache: Memo<Point, bool>,
 }
 
 impl Polygon {
@@ -20,8 +22,7 @@ impl Polygon {
         Polygon {
             points: Vec::<Point>::new(),
             verticals: Vec::<(Point, Point)>::new(),
-            cache: HashMap::<Point, bool>::new(),
-            cache_hits: 0,
+            cache: Memo::<Point, bool>::new(),
         }
     }
 
@@ -76,8 +77,7 @@ impl Polygon {
             Some(x) => {
                 // got ~250k cache-hits so was worth
                 //println!("Cache-HIT {:?}", &p);
-                self.cache_hits += 1;
-                return x.clone();
+                return x;
             }
             None => {
                 //println!("Checking point {:?}", p);
@@ -261,7 +261,7 @@ impl Polygon {
     }
 }
 
-pub fn run() {
+pub fn run(export: Option<&Path>) {
     let mut reader = BufReader::new(File::open("movie.txt").expect("reading file failed"));
     let mut line = String::new();
 
@@ -290,6 +290,7 @@ pub fn run() {
     let mut num_rect: i64 = 0;
     let mut valid_rect: i64 = 0;
     let mut big_a: i64 = 0;
+    let mut best = (0, 0);
 
     for i in 0..poly.points.len() {
         for j in i+1..poly.points.len() {
@@ -300,6 +301,7 @@ pub fn run() {
                     if x > big_a {
                         //println!("Found big a!");
                         big_a = x;
+                        best = (i, j);
                         //println!("BIG {} constructed from {:?},{:?}", big_a, &poly.points[i], &poly.points[j]);
                     }
                 }
@@ -308,12 +310,20 @@ pub fn run() {
         }
     }
     println!("Checked {} rects {} were valid", num_rect, valid_rect);
-    println!("Cache-hits: {}", poly.cache_hits);
+    println!("Cache: {}", poly.cache.stats());
     println!("Big A: {}", big_a);
     assert_ne!(big_a, 4646235780); // too high
     assert_ne!(big_a, 3642758717); // too high
     assert_ne!(big_a, 3440890272); // too high
 
+    if let Some(p) = export {
+        let mut svg = Svg::new();
+        svg.polygon(&poly.points, "lightgreen", "black");
+        svg.rect(poly.points[best.0], poly.points[best.1], "none", "red");
+        svg.write(p).expect("writing svg failed");
+        println!("Wrote {}", p.display());
+    }
+
     //let mut big_a: i64 = 0;
 
     //for i in 0..points.len() {
@@ -362,6 +372,15 @@ mod test {
         assert_eq!(p.contains(&(3,3)), true);
     }
 
+    #[test]
+    fn test_contains_cached() {
+        let mut p = setup_polygon();
+        assert_eq!(p.contains(&(3,3)), true);
+        assert_eq!(p.contains(&(3,3)), true);
+        let s = p.cache.stats();
+        assert_eq!((s.hits, s.misses), (1, 1));
+    }
+
     #[test]
     fn test_origin_contains() {
         let mut p = setup_polygon();
@@ -0,0 +1,163 @@
+// Numbers as digit arrays, most significant digit first. Digits are values
+// 0..base, not ascii, use from_ascii and to_ascii to cross over. The
+// repetition and subsequence helpers work on any slice so they are just as
+// happy with ascii bytes.
+
+pub fn to_digits(mut n: u64, base: u32) -> Vec<u8> {
+    assert!((2..=36).contains(&base), "base {} out of range", base);
+    let mut d = Vec::new();
+    loop {
+        d.push((n % base as u64) as u8);
+        n /= base as u64;
+        if n == 0 {
+            break;
+        }
+    }
+    d.reverse();
+    d
+}
+
+// None if a digit is too big for the base or the value overflows
+pub fn from_digits(d: &[u8], base: u32) -> Option<u64> {
+    d.iter().try_fold(0u64, |n, &x| {
+        if x as u32 >= base {
+            return None;
+        }
+        n.checked_mul(base as u64)?.checked_add(x as u64)
+    })
+}
+
+// 0-9 then a-z for bases past ten
+pub fn from_ascii(s: &[u8]) -> Option<Vec<u8>> {
+    s.iter().map(|&c| (c as char).to_digit(36).map(|v| v as u8)).collect()
+}
+
+pub fn to_ascii(d: &[u8]) -> Vec<u8> {
+    d.iter().map(|&v| char::from_digit(v as u32, 36).expect("digit out of range") as u8).collect()
+}
+
+pub fn increment(d: &mut Vec<u8>, base: u32) {
+    for x in d.iter_mut().rev() {
+        if (*x as u32) + 1 < base {
+            *x += 1;
+            return;
+        }
+        *x = 0;
+    }
+    // every digit carried
+    d.insert(0, 1);
+}
+
+// false if d was zero and is left alone
+pub fn decrement(d: &mut Vec<u8>, base: u32) -> bool {
+    if d.iter().all(|&x| x == 0) {
+        return false;
+    }
+    for x in d.iter_mut().rev() {
+        if *x > 0 {
+            *x -= 1;
+            break;
+        }
+        *x = (base - 1) as u8;
+    }
+    // 10 - 1 is 9 not 09
+    let lead = d.iter().take_while(|&&x| x == 0).count().min(d.len() - 1);
+    d.drain(..lead);
+    true
+}
+
+// d is exactly `times` copies of one block
+pub fn repeats<T: PartialEq>(d: &[T], times: usize) -> bool {
+    if times == 0 || d.is_empty() || !d.len().is_multiple_of(times) {
+        return false;
+    }
+    let n = d.len() / times;
+    d.chunks(n).all(|c| c == &d[..n])
+}
+
+// the length of the shortest block that d is two or more copies of
+pub fn repeated_block<T: PartialEq>(d: &[T]) -> Option<usize> {
+    (1..=d.len() / 2).find(|&n| d.len().is_multiple_of(n) && repeats(d, d.len() / n))
+}
+
+// Keep k items in order so the result is as large as possible. A monotonic
+// stack drops any item that is followed by a bigger one while there are
+// still items to spare.
+pub fn largest_subsequence<T: Ord + Copy>(d: &[T], k: usize) -> Vec<T> {
+    select(d, k, |top, x| top < x)
+}
+
+pub fn smallest_subsequence<T: Ord + Copy>(d: &[T], k: usize) -> Vec<T> {
+    select(d, k, |top, x| top > x)
+}
+
+fn select<T: Copy>(d: &[T], k: usize, worse: impl Fn(&T, &T) -> bool) -> Vec<T> {
+    assert!(k <= d.len(), "can't keep {} of {}", k, d.len());
+    let mut spare = d.len() - k;
+    let mut stack: Vec<T> = Vec::with_capacity(d.len());
+    for x in d {
+        while spare > 0 && stack.last().is_some_and(|top| worse(top, x)) {
+            stack.pop();
+            spare -= 1;
+        }
+        stack.push(*x);
+    }
+    stack.truncate(k);
+    stack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(to_digits(1012, 10), vec![1, 0, 1, 2]);
+        assert_eq!(to_digits(0, 10), vec![0]);
+        assert_eq!(to_digits(10, 2), vec![1, 0, 1, 0]);
+        assert_eq!(from_digits(&[1, 0, 1, 0], 2), Some(10));
+        assert_eq!(from_digits(&[2], 2), None);
+        assert_eq!(from_digits(&to_digits(u64::MAX, 16), 16), Some(u64::MAX));
+        assert_eq!(from_digits(&[1; 21], 10), None);
+        assert_eq!(from_ascii(b"9f"), Some(vec![9, 15]));
+        assert_eq!(from_ascii(b"1-2"), None);
+        assert_eq!(to_ascii(&[9, 15]), b"9f");
+    }
+
+    #[test]
+    fn test_increment_decrement() {
+        let mut d = vec![9, 9];
+        increment(&mut d, 10);
+        assert_eq!(d, vec![1, 0, 0]);
+        assert!(decrement(&mut d, 10));
+        assert_eq!(d, vec![9, 9]);
+        let mut d = vec![1, 0];
+        assert!(decrement(&mut d, 10));
+        assert_eq!(d, vec![9]);
+        let mut d = vec![0];
+        assert!(!decrement(&mut d, 10));
+        increment(&mut d, 2);
+        increment(&mut d, 2);
+        assert_eq!(d, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_repeats() {
+        assert!(repeats(b"6464", 2));
+        assert!(!repeats(b"111", 2));
+        assert!(repeats(b"111", 3));
+        assert_eq!(repeated_block(b"824824824"), Some(3));
+        assert_eq!(repeated_block(b"1111111"), Some(1));
+        assert_eq!(repeated_block(b"1212123"), None);
+        assert_eq!(repeated_block(b"7"), None);
+    }
+
+    #[test]
+    fn test_subsequence() {
+        assert_eq!(largest_subsequence(b"818181911112111", 12), b"888911112111");
+        assert_eq!(largest_subsequence(b"234234234234278", 12), b"434234234278");
+        assert_eq!(smallest_subsequence(&[3, 1, 2, 1], 2), vec![1, 1]);
+        assert_eq!(largest_subsequence(&[3, 1, 2, 1], 2), vec![3, 2]);
+        assert_eq!(largest_subsequence(&[5, 5], 2), vec![5, 5]);
+    }
+}
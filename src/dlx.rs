@@ -0,0 +1,250 @@
+// Exact cover with Knuth's Algorithm X on dancing links. Primary columns
+// must be covered exactly once, secondary columns at most once. Rows are
+// numbered in the order they were added.
+
+#[derive(Debug, Clone)]
+pub struct ExactCover {
+    columns: usize,
+    // node 0 is the root, 1..=columns are the column headers and the
+    // row nodes follow, all links are node indexes
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    col: Vec<usize>,
+    row: Vec<usize>,
+    size: Vec<usize>,
+    rows: usize,
+}
+
+impl ExactCover {
+    pub fn new(primary: usize, secondary: usize) -> Self {
+        let columns = primary + secondary;
+        let n = columns + 1;
+        let mut x = ExactCover {
+            columns,
+            left: (0..n).collect(),
+            right: (0..n).collect(),
+            up: (0..n).collect(),
+            down: (0..n).collect(),
+            col: (0..n).collect(),
+            row: vec![usize::MAX; n],
+            size: vec![0; n],
+            rows: 0,
+        };
+        // only primary headers go in the root list, so the search never
+        // picks a secondary column and a solution can leave them open
+        for c in 0..=primary {
+            x.right[c] = if c == primary { 0 } else { c + 1 };
+            x.left[c] = if c == 0 { primary } else { c - 1 };
+        }
+        x
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    // Add a row covering the given columns, primary columns are
+    // 0..primary and secondary ones follow. Returns the row number.
+    pub fn add_row(&mut self, columns: &[usize]) -> usize {
+        assert!(!columns.is_empty(), "a row has to cover something");
+        let id = self.rows;
+        let first = self.left.len();
+        for (k, &c) in columns.iter().enumerate() {
+            assert!(c < self.columns, "column {} out of range", c);
+            assert!(!columns[..k].contains(&c), "column {} twice in one row", c);
+            let h = c + 1;
+            let n = self.left.len();
+            self.left.push(if k == 0 { n } else { n - 1 });
+            self.right.push(first);
+            if k > 0 {
+                self.right[n - 1] = n;
+                self.left[first] = n;
+            }
+            self.up.push(self.up[h]);
+            self.down.push(h);
+            let above = self.up[h];
+            self.down[above] = n;
+            self.up[h] = n;
+            self.col.push(h);
+            self.row.push(id);
+            self.size[h] += 1;
+        }
+        self.rows += 1;
+        id
+    }
+
+    fn cover(&mut self, c: usize) {
+        let (l, r) = (self.left[c], self.right[c]);
+        self.right[l] = r;
+        self.left[r] = l;
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = d;
+                self.up[d] = u;
+                self.size[self.col[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                let (u, d) = (self.up[j], self.down[j]);
+                self.down[u] = j;
+                self.up[d] = j;
+                self.size[self.col[j]] += 1;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        let (l, r) = (self.left[c], self.right[c]);
+        self.right[l] = c;
+        self.left[r] = c;
+    }
+
+    // false once visit asks to stop
+    fn search(&mut self, partial: &mut Vec<usize>, visit: &mut dyn FnMut(&[usize]) -> bool) -> bool {
+        if self.right[0] == 0 {
+            return visit(partial);
+        }
+        // the column with the fewest candidate rows
+        let mut c = self.right[0];
+        let mut j = self.right[c];
+        while j != 0 {
+            if self.size[j] < self.size[c] {
+                c = j;
+            }
+            j = self.right[j];
+        }
+        if self.size[c] == 0 {
+            return true;
+        }
+
+        self.cover(c);
+        let mut go = true;
+        let mut r = self.down[c];
+        while r != c && go {
+            partial.push(self.row[r]);
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.col[j]);
+                j = self.right[j];
+            }
+            go = self.search(partial, visit);
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.col[j]);
+                j = self.left[j];
+            }
+            partial.pop();
+            r = self.down[r];
+        }
+        self.uncover(c);
+        go
+    }
+
+    // Call visit with the rows of each solution until it returns false.
+    // The links are restored afterwards so the solver can be reused.
+    pub fn each(&mut self, mut visit: impl FnMut(&[usize]) -> bool) {
+        let mut partial = Vec::new();
+        self.search(&mut partial, &mut visit);
+    }
+
+    pub fn first(&mut self) -> Option<Vec<usize>> {
+        let mut found = None;
+        self.each(|s| {
+            found = Some(s.to_vec());
+            false
+        });
+        found
+    }
+
+    pub fn count(&mut self) -> u64 {
+        let mut n = 0;
+        self.each(|_| {
+            n += 1;
+            true
+        });
+        n
+    }
+
+    // up to `limit` solutions, each with its rows sorted
+    pub fn solutions(&mut self, limit: usize) -> Vec<Vec<usize>> {
+        let mut all = Vec::new();
+        if limit == 0 {
+            return all;
+        }
+        self.each(|s| {
+            let mut s = s.to_vec();
+            s.sort();
+            all.push(s);
+            all.len() < limit
+        });
+        all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn knuth() -> ExactCover {
+        // the example from the dancing links paper
+        let mut x = ExactCover::new(7, 0);
+        for r in [vec![2, 4, 5], vec![0, 3, 6], vec![1, 2, 5], vec![0, 3], vec![1, 6], vec![3, 4, 6]] {
+            x.add_row(&r);
+        }
+        x
+    }
+
+    fn queens(n: usize) -> ExactCover {
+        // ranks and files are primary, both diagonal directions secondary
+        let mut x = ExactCover::new(2 * n, 2 * (2 * n - 1));
+        for r in 0..n {
+            for c in 0..n {
+                x.add_row(&[r, n + c, 2 * n + r + c, 4 * n - 1 + r + n - 1 - c]);
+            }
+        }
+        x
+    }
+
+    #[test]
+    fn test_knuth() {
+        let mut x = knuth();
+        assert_eq!(x.rows(), 6);
+        let mut s = x.first().unwrap();
+        s.sort();
+        assert_eq!(s, vec![0, 3, 4]);
+        assert_eq!(x.count(), 1);
+        // still usable after a search
+        assert_eq!(x.solutions(10), vec![vec![0, 3, 4]]);
+    }
+
+    #[test]
+    fn test_no_cover() {
+        let mut x = ExactCover::new(3, 0);
+        x.add_row(&[0, 1]);
+        x.add_row(&[1, 2]);
+        assert_eq!(x.first(), None);
+        assert_eq!(x.count(), 0);
+    }
+
+    #[test]
+    fn test_queens() {
+        assert_eq!(queens(4).count(), 2);
+        assert_eq!(queens(8).count(), 92);
+        let mut x = queens(8);
+        let some = x.solutions(5);
+        assert_eq!(some.len(), 5);
+        assert!(some.iter().all(|s| s.len() == 8));
+    }
+}
@@ -0,0 +1,352 @@
+// Pictures for debugging: grids as PPM or PNG images (optionally one
+// numbered file per step), shapes as SVG, and graphs as Graphviz DOT.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+pub const RED: Rgb = [220, 40, 40];
+pub const GREEN: Rgb = [40, 180, 60];
+
+// a colour on a black to `to` ramp, t in 0.0..=1.0
+pub fn ramp(to: Rgb, t: f64) -> Rgb {
+    let t = t.clamp(0.0, 1.0);
+    [(to[0] as f64 * t) as u8, (to[1] as f64 * t) as u8, (to[2] as f64 * t) as u8]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Image { width, height, pixels: vec![BLACK; width * height] }
+    }
+
+    // one scale x scale block of pixels per cell, rows may be ragged
+    pub fn from_grid<T>(rows: &[Vec<T>], scale: usize, color: impl Fn(&T) -> Rgb) -> Self {
+        let w = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let mut img = Image::new(w * scale, rows.len() * scale);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let c = color(cell);
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        img.set(x * scale + dx, y * scale + dy, c);
+                    }
+                }
+            }
+        }
+        img
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, c: Rgb) {
+        self.pixels[y * self.width + x] = c;
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for p in &self.pixels {
+            out.extend_from_slice(p);
+        }
+        out
+    }
+
+    // an uncompressed (stored deflate) PNG, big but needs no dependencies
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            // filter type none
+            raw.push(0);
+            for p in row {
+                raw.extend_from_slice(p);
+            }
+        }
+
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&(self.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bit rgb, default compression, filter and no interlace
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut out, b"IHDR", &ihdr);
+        png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    // picks the format from the extension, PPM unless it ends in .png
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("png") => fs::write(path, self.to_png()),
+            _ => fs::write(path, self.to_ppm()),
+        }
+    }
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in data {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() { vec![&[]] } else { data.chunks(65535).collect() };
+    for (i, block) in blocks.iter().enumerate() {
+        let last = (i == blocks.len() - 1) as u8;
+        out.push(last);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// Numbered images for an animation, floor.png becomes floor_0000.png,
+// floor_0001.png and so on
+#[derive(Debug)]
+pub struct Frames {
+    stem: PathBuf,
+    ext: String,
+    count: usize,
+}
+
+impl Frames {
+    pub fn new(path: &Path) -> Self {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("ppm").to_string();
+        Frames { stem: path.with_extension(""), ext, count: 0 }
+    }
+
+    pub fn next_path(&self) -> PathBuf {
+        let name = format!("{}_{:04}.{}", self.stem.display(), self.count, self.ext);
+        PathBuf::from(name)
+    }
+
+    pub fn push(&mut self, img: &Image) -> io::Result<PathBuf> {
+        let p = self.next_path();
+        img.write(&p)?;
+        self.count += 1;
+        Ok(p)
+    }
+}
+
+// A plain SVG drawing in puzzle coordinates, the view box is fitted to
+// whatever was drawn
+#[derive(Debug, Default)]
+pub struct Svg {
+    elements: Vec<String>,
+    min: (f64, f64),
+    max: (f64, f64),
+}
+
+impl Svg {
+    pub fn new() -> Self {
+        Svg { elements: Vec::new(), min: (f64::MAX, f64::MAX), max: (f64::MIN, f64::MIN) }
+    }
+
+    fn grow(&mut self, x: f64, y: f64) {
+        self.min = (self.min.0.min(x), self.min.1.min(y));
+        self.max = (self.max.0.max(x), self.max.1.max(y));
+    }
+
+    pub fn polygon(&mut self, points: &[(i64, i64)], fill: &str, stroke: &str) {
+        let mut pts = String::new();
+        for &(x, y) in points {
+            self.grow(x as f64, y as f64);
+            let _ = write!(pts, "{},{} ", x, y);
+        }
+        self.elements.push(format!(
+            "<polygon points=\"{}\" fill=\"{}\" stroke=\"{}\" vector-effect=\"non-scaling-stroke\"/>",
+            pts.trim_end(),
+            fill,
+            stroke
+        ));
+    }
+
+    // the rectangle with opposite corners a and b
+    pub fn rect(&mut self, a: (i64, i64), b: (i64, i64), fill: &str, stroke: &str) {
+        let (x, y) = (a.0.min(b.0), a.1.min(b.1));
+        let (w, h) = ((a.0 - b.0).abs(), (a.1 - b.1).abs());
+        self.grow(x as f64, y as f64);
+        self.grow((x + w) as f64, (y + h) as f64);
+        self.elements.push(format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\" vector-effect=\"non-scaling-stroke\"/>",
+            x, y, w, h, fill, stroke
+        ));
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (w, h) = ((self.max.0 - self.min.0).max(1.0), (self.max.1 - self.min.1).max(1.0));
+        // a little margin so strokes on the edge are not clipped
+        let m = w.max(h) * 0.02;
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"800\" height=\"{}\">\n",
+            self.min.0 - m,
+            self.min.1 - m,
+            w + 2.0 * m,
+            h + 2.0 * m,
+            (800.0 * h / w).round()
+        );
+        for e in &self.elements {
+            out.push_str("  ");
+            out.push_str(e);
+            out.push('\n');
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+// A Graphviz graph, `dot -Tsvg out.dot` to look at it
+#[derive(Debug)]
+pub struct Dot {
+    directed: bool,
+    lines: Vec<String>,
+}
+
+fn attr_list(attrs: &[(&str, &str)]) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+    let a: Vec<String> = attrs.iter().map(|(k, v)| format!("{}={:?}", k, v)).collect();
+    format!(" [{}]", a.join(", "))
+}
+
+impl Dot {
+    pub fn digraph() -> Self {
+        Dot { directed: true, lines: Vec::new() }
+    }
+
+    pub fn graph() -> Self {
+        Dot { directed: false, lines: Vec::new() }
+    }
+
+    pub fn node(&mut self, id: &str, attrs: &[(&str, &str)]) {
+        self.lines.push(format!("{:?}{};", id, attr_list(attrs)));
+    }
+
+    pub fn edge(&mut self, a: &str, b: &str, attrs: &[(&str, &str)]) {
+        let arrow = if self.directed { "->" } else { "--" };
+        self.lines.push(format!("{:?} {} {:?}{};", a, arrow, b, attr_list(attrs)));
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from(if self.directed { "digraph {\n" } else { "graph {\n" });
+        for l in &self.lines {
+            out.push_str("  ");
+            out.push_str(l);
+            out.push('\n');
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_dot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm() {
+        let img = Image::from_grid(&[vec![true, false]], 1, |&b| if b { WHITE } else { BLACK });
+        let ppm = img.to_ppm();
+        assert!(ppm.starts_with(b"P6\n2 1\n255\n"));
+        assert_eq!(&ppm[ppm.len() - 6..], &[255, 255, 255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_scale() {
+        let img = Image::from_grid(&[vec![1u8], vec![2u8]], 3, |&v| [v, v, v]);
+        assert_eq!((img.width, img.height), (3, 6));
+        assert_eq!(img.get(2, 2), [1, 1, 1]);
+        assert_eq!(img.get(0, 3), [2, 2, 2]);
+    }
+
+    #[test]
+    fn test_png() {
+        let png = Image::new(2, 2).to_png();
+        assert_eq!(&png[..8], &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n']);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        // the CRC of an empty IEND chunk is fixed
+        assert_eq!(&png[png.len() - 4..], &[0xae, 0x42, 0x60, 0x82]);
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_frames() {
+        let f = Frames::new(Path::new("out/floor.png"));
+        assert_eq!(f.next_path(), PathBuf::from("out/floor_0000.png"));
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut s = Svg::new();
+        s.polygon(&[(0, 0), (10, 0), (10, 5)], "none", "black");
+        s.rect((2, 1), (4, 3), "red", "none");
+        let out = s.to_svg();
+        assert!(out.contains("points=\"0,0 10,0 10,5\""));
+        assert!(out.contains("<rect x=\"2\" y=\"1\" width=\"2\" height=\"2\""));
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut d = Dot::digraph();
+        d.node("you", &[("color", "red")]);
+        d.edge("you", "out", &[]);
+        assert_eq!(d.to_dot(), "digraph {\n  \"you\" [color=\"red\"];\n  \"you\" -> \"out\";\n}\n");
+    }
+}
@@ -0,0 +1,438 @@
+use std::fmt;
+
+use crate::linalg::{Rational, solve_integer};
+
+// Integer linear programs over nonnegative integer variables. The LP
+// relaxation is solved exactly with a two phase rational simplex using
+// Bland's rule, so it never cycles, and integrality comes from depth first
+// branch and bound on the first fractional variable.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sense {
+    Le,
+    Eq,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub coeffs: Vec<i64>,
+    pub sense: Sense,
+    pub rhs: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IlpError {
+    // even the relaxation has no feasible point, these are the
+    // constraints phase one could not satisfy
+    Infeasible { violated: Vec<usize> },
+    // the relaxation is feasible but no integer point is
+    NoIntegerPoint,
+    Unbounded,
+    // branch and bound gave up, an unbounded region can go on forever
+    NodeLimit { nodes: usize },
+}
+
+impl fmt::Display for IlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IlpError::Infeasible { violated } => write!(f, "infeasible, constraints {:?} cannot be met", violated),
+            IlpError::NoIntegerPoint => write!(f, "feasible but no integer solution"),
+            IlpError::Unbounded => write!(f, "objective is unbounded"),
+            IlpError::NodeLimit { nodes } => write!(f, "gave up after {} branch and bound nodes", nodes),
+        }
+    }
+}
+
+impl std::error::Error for IlpError {}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IlpStats {
+    pub nodes: usize,
+    pub pivots: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LpSolution {
+    pub value: Rational,
+    pub x: Vec<Rational>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IlpSolution {
+    pub value: i64,
+    pub x: Vec<i64>,
+    pub stats: IlpStats,
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    vars: usize,
+    // always minimized, a maximize problem is stored negated
+    objective: Vec<i64>,
+    maximize: bool,
+    constraints: Vec<Constraint>,
+    node_limit: usize,
+}
+
+impl Problem {
+    pub fn minimize(objective: Vec<i64>) -> Self {
+        Problem { vars: objective.len(), objective, maximize: false, constraints: Vec::new(), node_limit: 1_000_000 }
+    }
+
+    pub fn maximize(objective: Vec<i64>) -> Self {
+        let objective = objective.into_iter().map(|c| -c).collect();
+        Problem { maximize: true, ..Problem::minimize(objective) }
+    }
+
+    pub fn constraint(&mut self, coeffs: Vec<i64>, sense: Sense, rhs: i64) -> &mut Self {
+        assert_eq!(coeffs.len(), self.vars, "one coefficient per variable");
+        self.constraints.push(Constraint { coeffs, sense, rhs });
+        self
+    }
+
+    // the most branch and bound nodes solve visits before giving up
+    pub fn node_limit(&mut self, nodes: usize) -> &mut Self {
+        self.node_limit = nodes;
+        self
+    }
+
+    fn report(&self, v: Rational) -> Rational {
+        if self.maximize { -v } else { v }
+    }
+
+    // the LP relaxation, variables only need to be nonnegative
+    pub fn relax(&self) -> Result<LpSolution, IlpError> {
+        let mut pivots = 0;
+        let lp = simplex(self.vars, &self.objective, &self.constraints, &mut pivots)?;
+        Ok(LpSolution { value: self.report(lp.value), ..lp })
+    }
+
+    pub fn solve(&self) -> Result<IlpSolution, IlpError> {
+        let mut stats = IlpStats::default();
+        let mut best: Option<(i64, Vec<i64>)> = None;
+        // each node is the extra bounds branched on so far
+        let mut stack: Vec<Vec<Constraint>> = vec![Vec::new()];
+
+        while let Some(bounds) = stack.pop() {
+            if stats.nodes == self.node_limit {
+                return Err(IlpError::NodeLimit { nodes: stats.nodes });
+            }
+            stats.nodes += 1;
+            let root = bounds.is_empty();
+            let mut all = self.constraints.clone();
+            all.extend(bounds.iter().cloned());
+
+            let lp = match simplex(self.vars, &self.objective, &all, &mut stats.pivots) {
+                Ok(lp) => lp,
+                Err(e) if root => return Err(e),
+                Err(_) => continue,
+            };
+            // Branching never ends on an unbounded region like 2x - 2y = 1,
+            // so check the equations have an integer solution at all
+            if root && !self.equations_integral() {
+                return Err(IlpError::NoIntegerPoint);
+            }
+            // the objective has integer coefficients so an integer point can
+            // do no better than the ceiling of the relaxation
+            if let Some((b, _)) = &best
+                && lp.value.ceil() >= *b as i128
+            {
+                continue;
+            }
+
+            match lp.x.iter().position(|v| !v.is_integer()) {
+                None => {
+                    let x: Vec<i64> = lp.x.iter().map(|v| v.num() as i64).collect();
+                    best = Some((lp.value.num() as i64, x));
+                }
+                Some(j) => {
+                    let v = lp.x[j];
+                    let mut unit = vec![0; self.vars];
+                    unit[j] = 1;
+                    let mut up = bounds.clone();
+                    up.push(Constraint { coeffs: unit.clone(), sense: Sense::Ge, rhs: v.ceil() as i64 });
+                    let mut down = bounds;
+                    down.push(Constraint { coeffs: unit, sense: Sense::Le, rhs: v.floor() as i64 });
+                    stack.push(up);
+                    stack.push(down);
+                }
+            }
+        }
+
+        let (value, x) = best.ok_or(IlpError::NoIntegerPoint)?;
+        let value = if self.maximize { -value } else { value };
+        Ok(IlpSolution { value, x, stats })
+    }
+
+    // the equality rows have an integer solution, ignoring signs
+    fn equations_integral(&self) -> bool {
+        let eqs: Vec<&Constraint> = self.constraints.iter().filter(|c| c.sense == Sense::Eq).collect();
+        if eqs.is_empty() {
+            return true;
+        }
+        let a: Vec<Vec<i64>> = eqs.iter().map(|c| c.coeffs.clone()).collect();
+        let b: Vec<i64> = eqs.iter().map(|c| c.rhs).collect();
+        solve_integer(&a, &b).is_some()
+    }
+}
+
+struct Tableau {
+    // constraint rows, the last entry of each is the right hand side
+    rows: Vec<Vec<Rational>>,
+    // reduced costs, the last entry is minus the objective value
+    obj: Vec<Rational>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn pivot(&mut self, r: usize, c: usize) {
+        let inv = Rational::ONE / self.rows[r][c];
+        for v in self.rows[r].iter_mut() {
+            *v = *v * inv;
+        }
+        let pivot = self.rows[r].clone();
+        let eliminate = |row: &mut Vec<Rational>| {
+            let f = row[c];
+            if !f.is_zero() {
+                for (v, p) in row.iter_mut().zip(&pivot) {
+                    *v = *v - f * *p;
+                }
+            }
+        };
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            if i != r {
+                eliminate(row);
+            }
+        }
+        eliminate(&mut self.obj);
+        self.basis[r] = c;
+    }
+
+    // Minimize over the first `allowed` columns. Bland's rule: enter the
+    // lowest column with a negative reduced cost, leave on the lowest ratio
+    // with ties going to the lowest basic column. False if unbounded.
+    fn optimize(&mut self, allowed: usize, pivots: &mut usize) -> bool {
+        let rhs = self.obj.len() - 1;
+        loop {
+            let Some(c) = (0..allowed).find(|&j| self.obj[j] < Rational::ZERO) else {
+                return true;
+            };
+            let mut leave: Option<(Rational, usize, usize)> = None;
+            for (r, row) in self.rows.iter().enumerate() {
+                if row[c] > Rational::ZERO {
+                    let key = (row[rhs] / row[c], self.basis[r], r);
+                    if leave.is_none_or(|l| (key.0, key.1) < (l.0, l.1)) {
+                        leave = Some(key);
+                    }
+                }
+            }
+            let Some((_, _, r)) = leave else {
+                return false;
+            };
+            self.pivot(r, c);
+            *pivots += 1;
+        }
+    }
+}
+
+// minimize objective . x subject to constraints, x >= 0
+fn simplex(vars: usize, objective: &[i64], constraints: &[Constraint], pivots: &mut usize) -> Result<LpSolution, IlpError> {
+    // flip rows so every right hand side is nonnegative
+    let norm: Vec<Constraint> = constraints
+        .iter()
+        .map(|k| {
+            if k.rhs >= 0 {
+                return k.clone();
+            }
+            let sense = match k.sense {
+                Sense::Le => Sense::Ge,
+                Sense::Eq => Sense::Eq,
+                Sense::Ge => Sense::Le,
+            };
+            Constraint { coeffs: k.coeffs.iter().map(|c| -c).collect(), sense, rhs: -k.rhs }
+        })
+        .collect();
+
+    // columns: variables, then a slack per inequality, then an artificial
+    // per row that has no slack to start the basis with
+    let slacks = norm.iter().filter(|k| k.sense != Sense::Eq).count();
+    let arts = norm.iter().filter(|k| k.sense != Sense::Le).count();
+    let art_start = vars + slacks;
+    let width = art_start + arts;
+
+    let mut t = Tableau { rows: Vec::new(), obj: vec![Rational::ZERO; width + 1], basis: Vec::new() };
+    // constraint index of each artificial column
+    let mut art_of = Vec::new();
+    let (mut s, mut a) = (vars, art_start);
+    for (i, k) in norm.iter().enumerate() {
+        let mut row = vec![Rational::ZERO; width + 1];
+        for (v, &c) in row.iter_mut().zip(&k.coeffs) {
+            *v = c.into();
+        }
+        row[width] = k.rhs.into();
+        match k.sense {
+            Sense::Le => {
+                row[s] = Rational::ONE;
+                t.basis.push(s);
+                s += 1;
+            }
+            Sense::Ge => {
+                row[s] = -Rational::ONE;
+                s += 1;
+                row[a] = Rational::ONE;
+                t.basis.push(a);
+                art_of.push(i);
+                a += 1;
+            }
+            Sense::Eq => {
+                row[a] = Rational::ONE;
+                t.basis.push(a);
+                art_of.push(i);
+                a += 1;
+            }
+        }
+        t.rows.push(row);
+    }
+
+    // phase one, minimize the sum of the artificials
+    for j in art_start..width {
+        t.obj[j] = Rational::ONE;
+    }
+    for r in 0..t.rows.len() {
+        if t.basis[r] >= art_start {
+            for (o, v) in t.obj.iter_mut().zip(&t.rows[r]) {
+                *o = *o - *v;
+            }
+        }
+    }
+    t.optimize(width, pivots);
+    if t.obj[width] < Rational::ZERO {
+        let mut violated: Vec<usize> = (0..t.rows.len())
+            .filter(|&r| t.basis[r] >= art_start && t.rows[r][width] > Rational::ZERO)
+            .map(|r| art_of[t.basis[r] - art_start])
+            .collect();
+        violated.sort();
+        return Err(IlpError::Infeasible { violated });
+    }
+
+    // drive any artificial still in the basis (at zero) out, a row with
+    // nothing else left in it was redundant
+    let mut r = 0;
+    while r < t.rows.len() {
+        if t.basis[r] >= art_start {
+            if let Some(c) = (0..art_start).find(|&c| !t.rows[r][c].is_zero()) {
+                t.pivot(r, c);
+            } else {
+                t.rows.remove(r);
+                t.basis.remove(r);
+                continue;
+            }
+        }
+        r += 1;
+    }
+
+    // phase two, price the real objective against the current basis
+    t.obj = vec![Rational::ZERO; width + 1];
+    for (o, &c) in t.obj.iter_mut().zip(objective) {
+        *o = c.into();
+    }
+    for r in 0..t.rows.len() {
+        let cb = t.obj[t.basis[r]];
+        if !cb.is_zero() {
+            for (o, v) in t.obj.iter_mut().zip(&t.rows[r]) {
+                *o = *o - cb * *v;
+            }
+        }
+    }
+    if !t.optimize(art_start, pivots) {
+        return Err(IlpError::Unbounded);
+    }
+
+    let mut x = vec![Rational::ZERO; vars];
+    for (r, &b) in t.basis.iter().enumerate() {
+        if b < vars {
+            x[b] = t.rows[r][width];
+        }
+    }
+    Ok(LpSolution { value: -t.obj[width], x })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relax() {
+        // max 3x + 2y, x + y <= 4, x + 3y <= 6, x <= 3
+        let mut p = Problem::maximize(vec![3, 2]);
+        p.constraint(vec![1, 1], Sense::Le, 4).constraint(vec![1, 3], Sense::Le, 6).constraint(vec![1, 0], Sense::Le, 3);
+        let lp = p.relax().unwrap();
+        assert_eq!(lp.value, Rational::from(11i64));
+        assert_eq!(lp.x, vec![Rational::from(3i64), Rational::from(1i64)]);
+    }
+
+    #[test]
+    fn test_branch() {
+        // max x + y, 2x + 2y <= 7 relaxes to 3.5
+        let mut p = Problem::maximize(vec![1, 1]);
+        p.constraint(vec![2, 2], Sense::Le, 7);
+        assert_eq!(p.relax().unwrap().value, Rational::new(7, 2));
+        let s = p.solve().unwrap();
+        assert_eq!(s.value, 3);
+        assert_eq!(s.x.iter().sum::<i64>(), 3);
+        assert!(s.stats.nodes > 1);
+    }
+
+    #[test]
+    fn test_buttons() {
+        // (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
+        let mut p = Problem::minimize(vec![1; 6]);
+        p.constraint(vec![0, 0, 0, 0, 1, 1], Sense::Eq, 3)
+            .constraint(vec![0, 1, 0, 0, 0, 1], Sense::Eq, 5)
+            .constraint(vec![0, 0, 1, 1, 1, 0], Sense::Eq, 4)
+            .constraint(vec![1, 1, 0, 1, 0, 0], Sense::Eq, 7);
+        let s = p.solve().unwrap();
+        assert_eq!(s.value, 10);
+        assert_eq!(s.x.iter().sum::<i64>(), 10);
+    }
+
+    #[test]
+    fn test_infeasible() {
+        let mut p = Problem::minimize(vec![1, 1]);
+        p.constraint(vec![1, 1], Sense::Ge, 5).constraint(vec![1, 0], Sense::Le, 1).constraint(vec![0, 1], Sense::Le, 1);
+        match p.solve() {
+            Err(IlpError::Infeasible { violated }) => assert_eq!(violated, vec![0]),
+            r => panic!("expected infeasible, got {:?}", r),
+        }
+        // 2x = 1 is fine fractionally
+        let mut p = Problem::minimize(vec![1]);
+        p.constraint(vec![2], Sense::Eq, 1);
+        assert_eq!(p.solve(), Err(IlpError::NoIntegerPoint));
+    }
+
+    #[test]
+    fn test_no_integer_point_unbounded() {
+        // 2x - 2y = 1 runs off to infinity with no integer point anywhere
+        let mut p = Problem::minimize(vec![1, 1]);
+        p.constraint(vec![2, -2], Sense::Eq, 1);
+        assert_eq!(p.solve(), Err(IlpError::NoIntegerPoint));
+        // the same as two inequalities gets past the equation check, so
+        // only the node limit stops it
+        let mut p = Problem::minimize(vec![1, 1]);
+        p.constraint(vec![2, -2], Sense::Le, 1).constraint(vec![2, -2], Sense::Ge, 1).node_limit(100);
+        assert_eq!(p.solve(), Err(IlpError::NodeLimit { nodes: 100 }));
+    }
+
+    #[test]
+    fn test_unbounded_and_redundant() {
+        let mut p = Problem::maximize(vec![1, 0]);
+        p.constraint(vec![0, 1], Sense::Le, 2);
+        assert_eq!(p.relax(), Err(IlpError::Unbounded));
+        // the same equation twice leaves an artificial stuck in the basis
+        let mut p = Problem::minimize(vec![1, 2]);
+        p.constraint(vec![1, 1], Sense::Eq, 2).constraint(vec![1, 1], Sense::Eq, 2).constraint(vec![-1, 0], Sense::Le, -1);
+        let s = p.solve().unwrap();
+        assert_eq!((s.value, s.x), (2, vec![2, 0]));
+    }
+}
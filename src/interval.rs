@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// An integer type that can bound an interval. Intervals are inclusive on
+// both ends so the whole range of the type can be represented, succ and
+// pred say where the type runs out instead of overflowing.
+pub trait Bound: Copy + Ord + fmt::Debug {
+    const MIN: Self;
+    const MAX: Self;
+    fn succ(self) -> Option<Self>;
+    fn pred(self) -> Option<Self>;
+    // number of values in lo..=hi
+    fn span(lo: Self, hi: Self) -> u128;
+}
+
+macro_rules! bound {
+    ($($t:ty),*) => {$(
+        impl Bound for $t {
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+            fn succ(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn pred(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+            fn span(lo: Self, hi: Self) -> u128 {
+                (hi as i128 - lo as i128) as u128 + 1
+            }
+        }
+    )*};
+}
+
+bound!(u8, u16, u32, u64, usize, i8, i16, i32, i64);
+
+// A set of values kept as disjoint, sorted, non-adjacent inclusive
+// intervals, so 1..=2 and 3..=4 are stored as 1..=4
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct IntervalSet<T: Bound> {
+    // start -> end
+    map: BTreeMap<T, T>,
+}
+
+impl<T: Bound> IntervalSet<T> {
+    pub fn new() -> Self {
+        IntervalSet { map: BTreeMap::new() }
+    }
+
+    // every value of T
+    pub fn full() -> Self {
+        let mut s = Self::new();
+        s.map.insert(T::MIN, T::MAX);
+        s
+    }
+
+    // Add lo..=hi, merging with anything it overlaps or touches
+    pub fn insert(&mut self, r: RangeInclusive<T>) {
+        let (mut lo, mut hi) = r.into_inner();
+        assert!(lo <= hi, "empty interval {:?}..={:?}", lo, hi);
+
+        // an interval starting at or before lo that reaches lo - 1
+        if let Some((&s, &e)) = self.map.range(..=lo).next_back()
+            && e.succ().is_none_or(|n| n >= lo)
+        {
+            lo = s;
+            hi = hi.max(e);
+        }
+        // everything starting inside lo..=hi + 1 gets swallowed
+        let last = hi.succ().unwrap_or(hi);
+        let inside: Vec<T> = self.map.range(lo..=last).map(|(&s, _)| s).collect();
+        for s in inside {
+            let e = self.map.remove(&s).unwrap();
+            hi = hi.max(e);
+        }
+        self.map.insert(lo, hi);
+    }
+
+    pub fn remove(&mut self, r: RangeInclusive<T>) {
+        let (lo, hi) = r.into_inner();
+        assert!(lo <= hi, "empty interval {:?}..={:?}", lo, hi);
+
+        // the interval covering lo may stick out on either side
+        if let Some((&s, &e)) = self.map.range(..lo).next_back()
+            && e >= lo
+        {
+            self.map.insert(s, lo.pred().unwrap());
+            if e > hi {
+                self.map.insert(hi.succ().unwrap(), e);
+            }
+        }
+        let inside: Vec<T> = self.map.range(lo..=hi).map(|(&s, _)| s).collect();
+        for s in inside {
+            let e = self.map.remove(&s).unwrap();
+            if e > hi {
+                self.map.insert(hi.succ().unwrap(), e);
+            }
+        }
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.map.range(..=x).next_back().is_some_and(|(_, &e)| e >= x)
+    }
+
+    // the interval holding x
+    pub fn interval_of(&self, x: T) -> Option<RangeInclusive<T>> {
+        self.map.range(..=x).next_back().filter(|(_, e)| **e >= x).map(|(&s, &e)| s..=e)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    // number of disjoint intervals
+    pub fn count(&self) -> usize {
+        self.map.len()
+    }
+
+    // number of values in the set, u128 since the full u64 range holds
+    // one more than u64::MAX
+    pub fn covered(&self) -> u128 {
+        self.map.iter().map(|(&s, &e)| T::span(s, e)).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.map.iter().map(|(&s, &e)| s..=e)
+    }
+
+    // the holes between consecutive intervals, not the space before the
+    // first or after the last
+    pub fn gaps(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.map.values().zip(self.map.keys().skip(1)).map(|(&e, &s)| e.succ().unwrap()..=s.pred().unwrap())
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut out = self.clone();
+        for r in other.iter() {
+            out.insert(r);
+        }
+        out
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        // walk both in order, each step drops whichever interval ends first
+        let mut out = Self::new();
+        let (mut a, mut b) = (self.iter().peekable(), other.iter().peekable());
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            let lo = *x.start().max(y.start());
+            let hi = *x.end().min(y.end());
+            if lo <= hi {
+                out.map.insert(lo, hi);
+            }
+            if x.end() < y.end() {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+        out
+    }
+
+    pub fn complement(&self) -> Self {
+        let mut out = Self::full();
+        for r in self.iter() {
+            out.remove(r);
+        }
+        out
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut out = self.clone();
+        for r in other.iter() {
+            out.remove(r);
+        }
+        out
+    }
+}
+
+impl<T: Bound> FromIterator<RangeInclusive<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut s = Self::new();
+        for r in iter {
+            s.insert(r);
+        }
+        s
+    }
+}
+
+impl<T: Bound> fmt::Debug for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(v: &[(u64, u64)]) -> IntervalSet<u64> {
+        v.iter().map(|&(a, b)| a..=b).collect()
+    }
+
+    fn ranges(s: &IntervalSet<u64>) -> Vec<(u64, u64)> {
+        s.iter().map(|r| r.into_inner()).collect()
+    }
+
+    #[test]
+    fn test_insert_coalesces() {
+        // the d5 sample ranges
+        let s = set(&[(3, 5), (10, 14), (16, 20), (12, 18)]);
+        assert_eq!(ranges(&s), vec![(3, 5), (10, 20)]);
+        assert_eq!(s.count(), 2);
+        assert_eq!(s.covered(), 14);
+        // touching intervals merge too
+        let s = set(&[(0, 1), (2, 3), (5, 5), (4, 4)]);
+        assert_eq!(ranges(&s), vec![(0, 5)]);
+        let s = set(&[(1, 10), (3, 4)]);
+        assert_eq!(ranges(&s), vec![(1, 10)]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let s = set(&[(3, 5), (10, 20)]);
+        let fresh: Vec<u64> = [1, 5, 8, 11, 17, 32].into_iter().filter(|&x| s.contains(x)).collect();
+        assert_eq!(fresh, vec![5, 11, 17]);
+        assert_eq!(s.interval_of(12), Some(10..=20));
+        assert_eq!(s.interval_of(9), None);
+        assert_eq!(s.gaps().collect::<Vec<_>>(), vec![6..=9]);
+    }
+
+    #[test]
+    fn test_remove_and_sets() {
+        let mut s = set(&[(0, 10), (20, 30)]);
+        s.remove(5..=22);
+        assert_eq!(ranges(&s), vec![(0, 4), (23, 30)]);
+        s.remove(2..=2);
+        assert_eq!(ranges(&s), vec![(0, 1), (3, 4), (23, 30)]);
+
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25)]);
+        assert_eq!(ranges(&a.intersection(&b)), vec![(5, 10), (20, 25)]);
+        assert_eq!(ranges(&a.union(&b)), vec![(0, 30)]);
+        assert_eq!(ranges(&a.difference(&b)), vec![(0, 4), (26, 30)]);
+        assert_eq!(ranges(&b.complement()), vec![(0, 4), (26, u64::MAX)]);
+    }
+
+    #[test]
+    fn test_edges() {
+        let mut s = set(&[(u64::MAX - 1, u64::MAX), (0, 0)]);
+        s.insert(1..=u64::MAX - 2);
+        assert_eq!(ranges(&s), vec![(0, u64::MAX)]);
+        assert_eq!(s.covered(), u64::MAX as u128 + 1);
+        assert!(s.complement().is_empty());
+        s.remove(u64::MAX..=u64::MAX);
+        assert!(!s.contains(u64::MAX));
+        let n: IntervalSet<i8> = [-128..=-100, 100..=127].into_iter().collect();
+        assert_eq!(n.complement().iter().collect::<Vec<_>>(), vec![-99..=99]);
+        assert_eq!(n.covered(), 57);
+    }
+}
@@ -0,0 +1,595 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::bitset::BitSet;
+
+// ---------------------------------------------------------------------------
+// GF(2)
+// ---------------------------------------------------------------------------
+
+// A matrix over GF(2), one BitSet per row with bit c for column c
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gf2Matrix {
+    rows: Vec<BitSet>,
+    cols: usize,
+}
+
+// Every solution of a GF(2) system is the particular solution xor any
+// combination of the null space basis
+#[derive(Debug, Clone)]
+pub struct Gf2Solution {
+    pub particular: BitSet,
+    pub null_space: Vec<BitSet>,
+}
+
+impl Gf2Matrix {
+    pub fn new(cols: usize) -> Self {
+        Gf2Matrix { rows: Vec::new(), cols }
+    }
+
+    pub fn from_rows(rows: Vec<BitSet>, cols: usize) -> Self {
+        Gf2Matrix { rows, cols }
+    }
+
+    // Build the matrix whose column c is columns[c]. Button puzzles come
+    // this way round, a button is the set of lights it toggles.
+    pub fn from_columns(columns: &[BitSet], rows: usize) -> Self {
+        let mut m = Gf2Matrix { rows: vec![BitSet::new(); rows], cols: columns.len() };
+        for (c, col) in columns.iter().enumerate() {
+            for r in col.iter() {
+                assert!(r < rows, "column {} has bit {} past {} rows", c, r, rows);
+                m.rows[r].insert(c);
+            }
+        }
+        m
+    }
+
+    pub fn push_row(&mut self, row: BitSet) {
+        self.rows.push(row);
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, r: usize, c: usize) -> bool {
+        self.rows[r].contains(c)
+    }
+
+    // Reduced row echelon form in place, returns the pivot column of each
+    // nonzero row. Bits at or past `cols` ride along, that is how the
+    // right hand side gets carried through a solve.
+    fn reduce(&mut self) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        let mut r = 0;
+        for c in 0..self.cols {
+            let Some(p) = (r..self.rows.len()).find(|&i| self.rows[i].contains(c)) else {
+                continue;
+            };
+            self.rows.swap(r, p);
+            let pivot = self.rows[r].clone();
+            for i in 0..self.rows.len() {
+                if i != r && self.rows[i].contains(c) {
+                    self.rows[i] ^= &pivot;
+                }
+            }
+            pivots.push(c);
+            r += 1;
+        }
+        pivots
+    }
+
+    pub fn rank(&self) -> usize {
+        self.clone().reduce().len()
+    }
+
+    pub fn null_space(&self) -> Vec<BitSet> {
+        let mut m = self.clone();
+        let pivots = m.reduce();
+        m.null_basis(&pivots)
+    }
+
+    // expects self to be reduced with the given pivots
+    fn null_basis(&self, pivots: &[usize]) -> Vec<BitSet> {
+        let mut basis = Vec::new();
+        for f in (0..self.cols).filter(|c| !pivots.contains(c)) {
+            let mut v = BitSet::new();
+            v.insert(f);
+            for (r, &p) in pivots.iter().enumerate() {
+                if self.rows[r].contains(f) {
+                    v.insert(p);
+                }
+            }
+            basis.push(v);
+        }
+        basis
+    }
+
+    // Solve self * x = b, None if the system is inconsistent
+    pub fn solve(&self, b: &BitSet) -> Option<Gf2Solution> {
+        // augment with b in column `cols`
+        let mut m = self.clone();
+        for (r, row) in m.rows.iter_mut().enumerate() {
+            row.remove(self.cols);
+            if b.contains(r) {
+                row.insert(self.cols);
+            }
+        }
+        // b asks for a row we don't have
+        if b.iter().any(|r| r >= self.rows.len()) {
+            return None;
+        }
+        let pivots = m.reduce();
+        // a zero row that still carries b
+        if m.rows[pivots.len()..].iter().any(|row| row.contains(self.cols)) {
+            return None;
+        }
+        let mut particular = BitSet::new();
+        for (r, &p) in pivots.iter().enumerate() {
+            if m.rows[r].contains(self.cols) {
+                particular.insert(p);
+            }
+        }
+        Some(Gf2Solution { particular, null_space: m.null_basis(&pivots) })
+    }
+}
+
+impl Gf2Solution {
+    pub fn count(&self) -> u128 {
+        1u128 << self.null_space.len()
+    }
+
+    // All 2^k solutions, fine while the null space stays small
+    pub fn iter(&self) -> impl Iterator<Item = BitSet> + '_ {
+        assert!(self.null_space.len() < 64, "null space too big to enumerate");
+        (0u64..1 << self.null_space.len()).map(move |mask| {
+            let mut x = self.particular.clone();
+            for (i, v) in self.null_space.iter().enumerate() {
+                if mask & (1 << i) != 0 {
+                    x ^= v;
+                }
+            }
+            x
+        })
+    }
+
+    // the solution with the fewest bits set
+    pub fn min_weight(&self) -> BitSet {
+        self.iter().min_by_key(|x| x.len()).unwrap()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Rationals
+// ---------------------------------------------------------------------------
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// An exact fraction, always in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { num: 0, den: 1 };
+    pub const ONE: Rational = Rational { num: 1, den: 1 };
+
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "zero denominator");
+        let g = gcd(num, den).max(1);
+        let s = if den < 0 { -1 } else { 1 };
+        Rational { num: s * num / g, den: s * den / g }
+    }
+
+    pub fn num(&self) -> i128 {
+        self.num
+    }
+
+    pub fn den(&self) -> i128 {
+        self.den
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.den == 1
+    }
+
+    pub fn floor(&self) -> i128 {
+        self.num.div_euclid(self.den)
+    }
+
+    pub fn ceil(&self) -> i128 {
+        -(-self.num).div_euclid(self.den)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Self {
+        Rational { num: n as i128, den: 1 }
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(n: i128) -> Self {
+        Rational { num: n, den: 1 }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+    fn add(self, o: Rational) -> Rational {
+        Rational::new(self.num * o.den + o.num * self.den, self.den * o.den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+    fn sub(self, o: Rational) -> Rational {
+        Rational::new(self.num * o.den - o.num * self.den, self.den * o.den)
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+    fn mul(self, o: Rational) -> Rational {
+        Rational::new(self.num * o.num, self.den * o.den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+    fn div(self, o: Rational) -> Rational {
+        assert!(!o.is_zero(), "division by zero");
+        Rational::new(self.num * o.den, self.den * o.num)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        Rational { num: -self.num, den: self.den }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, o: &Rational) -> Ordering {
+        (self.num * o.den).cmp(&(o.num * self.den))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, o: &Rational) -> Option<Ordering> {
+        Some(self.cmp(o))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Exact elimination
+// ---------------------------------------------------------------------------
+
+// Reduced row echelon form over the rationals in place, returns the pivot
+// column of each nonzero row. Only the first `cols` columns are pivoted
+// on so an augmented right hand side can ride along.
+pub fn rref(m: &mut [Vec<Rational>], cols: usize) -> Vec<usize> {
+    let mut pivots = Vec::new();
+    let mut r = 0;
+    for c in 0..cols {
+        let Some(p) = (r..m.len()).find(|&i| !m[i][c].is_zero()) else {
+            continue;
+        };
+        m.swap(r, p);
+        let inv = Rational::ONE / m[r][c];
+        for v in m[r].iter_mut() {
+            *v = *v * inv;
+        }
+        for i in 0..m.len() {
+            if i != r && !m[i][c].is_zero() {
+                let f = m[i][c];
+                for k in 0..m[i].len() {
+                    let d = f * m[r][k];
+                    m[i][k] = m[i][k] - d;
+                }
+            }
+        }
+        pivots.push(c);
+        r += 1;
+    }
+    pivots
+}
+
+pub fn rank(a: &[Vec<i64>]) -> usize {
+    let cols = a.first().map_or(0, |r| r.len());
+    let mut m: Vec<Vec<Rational>> = a.iter().map(|r| r.iter().map(|&v| v.into()).collect()).collect();
+    rref(&mut m, cols).len()
+}
+
+// x = particular + any combination of the null space basis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution<T> {
+    pub particular: Vec<T>,
+    pub null_space: Vec<Vec<T>>,
+}
+
+// Solve a * x = b over the rationals
+pub fn solve_rational(a: &[Vec<i64>], b: &[i64]) -> Option<Solution<Rational>> {
+    assert_eq!(a.len(), b.len(), "one right hand side per row");
+    let cols = a.first().map_or(0, |r| r.len());
+    let mut m: Vec<Vec<Rational>> = a
+        .iter()
+        .zip(b)
+        .map(|(row, &bv)| row.iter().chain([&bv]).map(|&v| v.into()).collect())
+        .collect();
+    let pivots = rref(&mut m, cols);
+    if m[pivots.len()..].iter().any(|row| !row[cols].is_zero()) {
+        return None;
+    }
+
+    let mut particular = vec![Rational::ZERO; cols];
+    for (r, &p) in pivots.iter().enumerate() {
+        particular[p] = m[r][cols];
+    }
+    let mut null_space = Vec::new();
+    for f in (0..cols).filter(|c| !pivots.contains(c)) {
+        let mut v = vec![Rational::ZERO; cols];
+        v[f] = Rational::ONE;
+        for (r, &p) in pivots.iter().enumerate() {
+            v[p] = -m[r][f];
+        }
+        null_space.push(v);
+    }
+    Some(Solution { particular, null_space })
+}
+
+// ---------------------------------------------------------------------------
+// Hermite normal form
+// ---------------------------------------------------------------------------
+
+// (g, x, y) with a*x + b*y = g = gcd(a, b) >= 0
+fn ext_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0.div_euclid(r1);
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 { (-r0, -x0, -y0) } else { (r0, x0, y0) }
+}
+
+// Column style Hermite normal form, a * u = h with u unimodular. The first
+// `pivots.len()` columns of h are lower triangular with positive pivots at
+// increasing rows, everything after them is zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hermite {
+    pub h: Vec<Vec<i64>>,
+    pub u: Vec<Vec<i64>>,
+    // pivot row of column k
+    pub pivots: Vec<usize>,
+}
+
+// apply the 2x2 unimodular column op [[p, q], [r, s]] to columns i and j:
+// ci' = p*ci + r*cj, cj' = q*ci + s*cj
+fn column_op(m: &mut [Vec<i64>], i: usize, j: usize, p: i64, q: i64, r: i64, s: i64) {
+    for row in m.iter_mut() {
+        let (a, b) = (row[i], row[j]);
+        row[i] = p * a + r * b;
+        row[j] = q * a + s * b;
+    }
+}
+
+pub fn hermite(a: &[Vec<i64>]) -> Hermite {
+    let n = a.first().map_or(0, |r| r.len());
+    let mut h = a.to_vec();
+    let mut u: Vec<Vec<i64>> = (0..n).map(|i| (0..n).map(|j| (i == j) as i64).collect()).collect();
+    let mut pivots = Vec::new();
+
+    for row in 0..h.len() {
+        let col = pivots.len();
+        if col == n {
+            break;
+        }
+        // fold every entry right of col into col with gcd steps
+        for j in col + 1..n {
+            if h[row][j] == 0 {
+                continue;
+            }
+            let (g, x, y) = ext_gcd(h[row][col], h[row][j]);
+            let (p, q) = (h[row][col] / g, h[row][j] / g);
+            column_op(&mut h, col, j, x, -q, y, p);
+            column_op(&mut u, col, j, x, -q, y, p);
+        }
+        if h[row][col] == 0 {
+            continue;
+        }
+        if h[row][col] < 0 {
+            for m in [&mut h, &mut u] {
+                m.iter_mut().for_each(|r| r[col] = -r[col]);
+            }
+        }
+        // reduce the earlier columns in this row into [0, pivot)
+        for k in 0..col {
+            let q = h[row][k].div_euclid(h[row][col]);
+            if q != 0 {
+                column_op(&mut h, k, col, 1, 0, -q, 1);
+                column_op(&mut u, k, col, 1, 0, -q, 1);
+            }
+        }
+        pivots.push(row);
+    }
+    Hermite { h, u, pivots }
+}
+
+// Solve a * x = b over the integers. The null space is a lattice basis,
+// every integer solution is the particular one plus an integer combination.
+pub fn solve_integer(a: &[Vec<i64>], b: &[i64]) -> Option<Solution<i64>> {
+    assert_eq!(a.len(), b.len(), "one right hand side per row");
+    let n = a.first().map_or(0, |r| r.len());
+    let hnf = hermite(a);
+    let k = hnf.pivots.len();
+
+    // forward substitution on h * y = b, rows without a pivot only check
+    let mut y = vec![0i64; k];
+    let mut next = 0;
+    for (r, &br) in b.iter().enumerate() {
+        let s: i64 = (0..next).map(|j| hnf.h[r][j] * y[j]).sum();
+        if next < k && hnf.pivots[next] == r {
+            let d = hnf.h[r][next];
+            if (br - s) % d != 0 {
+                return None;
+            }
+            y[next] = (br - s) / d;
+            next += 1;
+        } else if s != br {
+            return None;
+        }
+    }
+
+    let particular = (0..n).map(|i| (0..k).map(|j| hnf.u[i][j] * y[j]).sum()).collect();
+    let null_space = (k..n).map(|j| (0..n).map(|i| hnf.u[i][j]).collect()).collect();
+    Some(Solution { particular, null_space })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(v: &[usize]) -> BitSet {
+        v.iter().copied().collect()
+    }
+
+    fn mul(a: &[Vec<i64>], x: &[i64]) -> Vec<i64> {
+        a.iter().map(|r| r.iter().zip(x).map(|(p, q)| p * q).sum()).collect()
+    }
+
+    #[test]
+    fn test_gf2_buttons() {
+        // [.##.] (3) (1,3) (2) (2,3) (0,2) (0,1)
+        let buttons = [bits(&[3]), bits(&[1, 3]), bits(&[2]), bits(&[2, 3]), bits(&[0, 2]), bits(&[0, 1])];
+        let m = Gf2Matrix::from_columns(&buttons, 4);
+        assert_eq!(m.rank(), 4);
+        let s = m.solve(&bits(&[1, 2])).unwrap();
+        assert_eq!(s.null_space.len(), 2);
+        assert_eq!(s.count(), 4);
+        for x in s.iter() {
+            let mut lights = BitSet::new();
+            for b in x.iter() {
+                lights ^= &buttons[b];
+            }
+            assert_eq!(lights, bits(&[1, 2]));
+        }
+        assert_eq!(s.min_weight().len(), 2);
+    }
+
+    #[test]
+    fn test_gf2_by_rows() {
+        let mut m = Gf2Matrix::new(3);
+        m.push_row(bits(&[0, 2]));
+        m.push_row(bits(&[1]));
+        assert_eq!((m.rows(), m.cols()), (2, 3));
+        assert!(m.get(0, 2) && !m.get(1, 2));
+        assert_eq!(m, Gf2Matrix::from_rows(vec![bits(&[0, 2]), bits(&[1])], 3));
+        assert_eq!(m, Gf2Matrix::from_columns(&[bits(&[0]), bits(&[1]), bits(&[0])], 2));
+    }
+
+    #[test]
+    fn test_gf2_inconsistent() {
+        // two buttons that both toggle lights 0 and 1 together
+        let m = Gf2Matrix::from_columns(&[bits(&[0, 1]), bits(&[0, 1])], 2);
+        assert_eq!(m.rank(), 1);
+        assert!(m.solve(&bits(&[0])).is_none());
+        assert!(m.solve(&bits(&[5])).is_none());
+        assert_eq!(m.null_space(), vec![bits(&[0, 1])]);
+    }
+
+    #[test]
+    fn test_rational() {
+        let a = Rational::new(6, -4);
+        assert_eq!((a.num(), a.den()), (-3, 2));
+        assert_eq!(a + Rational::new(1, 2), Rational::from(-1i64));
+        assert_eq!((a.floor(), a.ceil()), (-2, -1));
+        assert!(a < Rational::ZERO);
+        assert_eq!(a.to_string(), "-3/2");
+        assert_eq!(a.to_f64(), -1.5);
+    }
+
+    #[test]
+    fn test_solve_rational() {
+        let a = vec![vec![2, 1], vec![1, 3]];
+        let s = solve_rational(&a, &[3, 5]).unwrap();
+        assert_eq!(s.particular, vec![Rational::new(4, 5), Rational::new(7, 5)]);
+        assert!(s.null_space.is_empty());
+        assert!(solve_rational(&[vec![1, 1], vec![2, 2]], &[1, 3]).is_none());
+        assert_eq!(rank(&[vec![1, 1], vec![2, 2]]), 1);
+    }
+
+    #[test]
+    fn test_hermite() {
+        let a = vec![vec![2, 4, 4], vec![-6, 6, 12], vec![10, -4, -16]];
+        let hnf = hermite(&a);
+        // a * u == h
+        for (row, hrow) in a.iter().zip(&hnf.h) {
+            for (c, &hv) in hrow.iter().enumerate() {
+                let v: i64 = row.iter().zip(&hnf.u).map(|(x, urow)| x * urow[c]).sum();
+                assert_eq!(v, hv);
+            }
+        }
+        for (k, &r) in hnf.pivots.iter().enumerate() {
+            assert!(hnf.h[r][k] > 0);
+            assert!((k + 1..3).all(|j| hnf.h[r][j] == 0));
+            assert!((0..k).all(|j| hnf.h[r][j] >= 0 && hnf.h[r][j] < hnf.h[r][k]));
+        }
+    }
+
+    #[test]
+    fn test_solve_integer() {
+        // counters 3,5,4,7 from the d10 sample
+        let a = vec![
+            vec![0, 0, 0, 0, 1, 1],
+            vec![0, 1, 0, 0, 0, 1],
+            vec![0, 0, 1, 1, 1, 0],
+            vec![1, 1, 0, 1, 0, 0],
+        ];
+        let b = [3, 5, 4, 7];
+        let s = solve_integer(&a, &b).unwrap();
+        assert_eq!(mul(&a, &s.particular), b);
+        assert_eq!(s.null_space.len(), 2);
+        for v in &s.null_space {
+            assert_eq!(mul(&a, v), vec![0; 4]);
+        }
+        // 2x = 3 has a rational answer but no integer one
+        assert!(solve_integer(&[vec![2]], &[3]).is_none());
+        assert!(solve_integer(&[vec![1, 1], vec![1, 1]], &[1, 2]).is_none());
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{Command, arg, value_parser};
 
 mod d1;
@@ -9,6 +11,49 @@ mod d6;
 mod d7;
 mod d8;
 mod d9;
+mod d12;
+
+mod answer;
+// d4 builds a Moore grid and updates it in place
+#[allow(dead_code)]
+mod automaton;
+mod bitset;
+// d3 wants the largest subsequence, d2 counts repeats by arithmetic
+#[allow(dead_code)]
+mod digits;
+// no day solves by exact cover yet
+#[allow(dead_code)]
+mod dlx;
+// Image::get and Dot::digraph are for tests and d11
+#[allow(dead_code)]
+mod export;
+// only d10 part two solves integer programs so far
+#[allow(dead_code)]
+mod ilp;
+// d2 and d5 only build sets up and query them
+#[allow(dead_code)]
+mod interval;
+// only the d10 bins and ilp solve systems so far
+#[allow(dead_code)]
+mod linalg;
+// d9 uses a plain unbounded cache, d11 recurses through one
+#[allow(dead_code)]
+mod memo;
+// the days use a few combinators each, the rest serve the d10 and d11 grammars
+#[allow(dead_code)]
+mod parse;
+// only the d10 bins search so far
+#[allow(dead_code)]
+mod search;
+// d12 packs shapes but never compares or measures them
+#[allow(dead_code)]
+mod shape;
+// aoc only reaches it through BitSet, Counters is for the d10 bins
+#[allow(dead_code)]
+mod smallvec;
+// d8 only joins and reads the merge events
+#[allow(dead_code)]
+mod unionfind;
 
 fn main() {
     let ap = Command::new("aoc")
@@ -16,23 +61,54 @@ fn main() {
         .about("Runs aoc answers")
         .arg_required_else_help(true)
         .arg(arg!(<module> "The module to run").value_parser(value_parser!(String)))
+        .arg(arg!(--"dial-size" <n> "Clicks around the d1 dial").default_value("100").value_parser(value_parser!(i64)))
+        .arg(arg!(--start <n> "Where the d1 dial starts").default_value("50").value_parser(value_parser!(i64)))
+        .arg(
+            arg!(--targets <marks> "Comma separated d1 dial positions that score")
+                .default_value("0")
+                .value_delimiter(',')
+                .value_parser(value_parser!(i64)),
+        )
+        .arg(
+            arg!(--rule <rule> "The d2 invalid id rule: repeat:K, repeat-at-least:K or palindrome")
+                .required(false)
+                .value_parser(value_parser!(d2::InvalidRule)),
+        )
+        .arg(
+            arg!(--base <n> "The base d2 looks for repeated digits in, ids are always read as decimal")
+                .default_value("10")
+                .value_parser(value_parser!(u32).range(2..=36)),
+        )
+        .arg(arg!(--merge "Count d2 ids that are in overlapping ranges once"))
+        .arg(
+            arg!(--export <path> "Write a picture of the puzzle (d4, d7, d8, d9)")
+                .required(false)
+                .value_parser(value_parser!(PathBuf)),
+        )
         .get_matches();
+    let export = ap.get_one::<PathBuf>("export").map(|p| p.as_path());
     match ap
         .get_one::<String>("module")
         .expect("Module is required")
         .as_str()
     {
         "d1" => {
-            d1::run();
+            let dial = d1::Dial::new(
+                *ap.get_one::<i64>("dial-size").unwrap(),
+                *ap.get_one::<i64>("start").unwrap(),
+                ap.get_many::<i64>("targets").unwrap().copied().collect(),
+            )
+            .unwrap_or_else(|e| panic!("{}", e));
+            d1::run(&dial);
         }
         "d2" => {
-            d2::run();
+            d2::run(ap.get_one::<d2::InvalidRule>("rule"), *ap.get_one::<u32>("base").unwrap(), ap.get_flag("merge"));
         }
         "d3" => {
             d3::run();
         }
         "d4" => {
-            d4::run();
+            d4::run(export);
         }
         "d5" => {
             d5::run();
@@ -41,13 +117,16 @@ fn main() {
             d6::run();
         }
         "d7" => {
-            d7::run();
+            d7::run(export);
         }
         "d8" => {
-            d8::run();
+            d8::run(export);
         }
         "d9" => {
-            d9::run();
+            d9::run(export);
+        }
+        "d12" => {
+            d12::run();
         }
         x => {
             panic!("No module {}", x);
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub len: usize,
+}
+
+impl fmt::Display for MemoStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.hits + self.misses;
+        let rate = if total == 0 { 0.0 } else { self.hits as f64 * 100.0 / total as f64 };
+        write!(
+            f,
+            "{} hits {} misses ({:.1}% hit rate) {} entries {} evictions",
+            self.hits, self.misses, rate, self.len, self.evictions
+        )
+    }
+}
+
+// A key to value cache that counts hits and misses. With a capacity the
+// least recently used entry is evicted to make room.
+#[derive(Debug)]
+pub struct Memo<K, V> {
+    // value and the tick it was last touched
+    map: HashMap<K, (V, u64)>,
+    // tick -> key, oldest first, only kept when bounded
+    lru: BTreeMap<u64, K>,
+    capacity: Option<usize>,
+    tick: u64,
+    stats: MemoStats,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Memo {
+            map: HashMap::new(),
+            lru: BTreeMap::new(),
+            capacity: None,
+            tick: 0,
+            stats: MemoStats::default(),
+        }
+    }
+
+    // keep at most `capacity` entries
+    pub fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "a bounded memo needs room for one entry");
+        let mut m = Self::new();
+        m.capacity = Some(capacity);
+        m
+    }
+
+    fn touch(&mut self, key: &K) {
+        if self.capacity.is_none() {
+            return;
+        }
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.map.get_mut(key) {
+            self.lru.remove(&entry.1);
+            entry.1 = tick;
+            self.lru.insert(tick, key.clone());
+        }
+    }
+
+    // counts as a hit or a miss
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        if self.map.contains_key(key) {
+            self.stats.hits += 1;
+            self.touch(key);
+            self.map.get(key).map(|(v, _)| v.clone())
+        } else {
+            self.stats.misses += 1;
+            None
+        }
+    }
+
+    // look without touching the statistics or the eviction order
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(|(v, _)| v)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if let Some((_, tick)) = self.map.get(&key) {
+            // overwriting, the key moves to the back of the queue below
+            self.lru.remove(tick);
+        } else if let Some(cap) = self.capacity
+            && self.map.len() >= cap
+            && let Some((_, old)) = self.lru.pop_first()
+        {
+            self.map.remove(&old);
+            self.stats.evictions += 1;
+        }
+        self.map.insert(key.clone(), (value, 0));
+        self.touch(&key);
+    }
+
+    // Return the cached value or compute and cache it. `f` gets the memo
+    // back so a recursive function can keep using it for subproblems.
+    pub fn get_or_compute(&mut self, key: K, f: impl FnOnce(&mut Self) -> V) -> V {
+        if let Some(v) = self.get(&key) {
+            return v;
+        }
+        let v = f(self);
+        self.insert(key, v.clone());
+        v
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.lru.clear();
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        MemoStats { len: self.map.len(), ..self.stats }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// A function wrapped with its own memo
+pub struct MemoFn<K, V, F: Fn(&K) -> V> {
+    memo: Memo<K, V>,
+    f: F,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone, F: Fn(&K) -> V> MemoFn<K, V, F> {
+    pub fn new(f: F) -> Self {
+        MemoFn { memo: Memo::new(), f }
+    }
+
+    pub fn bounded(capacity: usize, f: F) -> Self {
+        MemoFn { memo: Memo::bounded(capacity), f }
+    }
+
+    pub fn call(&mut self, key: &K) -> V {
+        let f = &self.f;
+        self.memo.get_or_compute(key.clone(), |_| f(key))
+    }
+
+    pub fn stats(&self) -> MemoStats {
+        self.memo.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fib(n: u64, memo: &mut Memo<u64, u64>) -> u64 {
+        if n < 2 {
+            return n;
+        }
+        memo.get_or_compute(n, |memo| fib(n - 1, memo) + fib(n - 2, memo))
+    }
+
+    #[test]
+    fn test_recursive() {
+        let mut memo = Memo::new();
+        assert_eq!(fib(90, &mut memo), 2880067194370816120);
+        let s = memo.stats();
+        assert_eq!(s.misses, 89);
+        assert_eq!(s.len, 89);
+        assert_eq!(s.hits, 87);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut memo = Memo::bounded(2);
+        memo.insert('a', 1);
+        memo.insert('b', 2);
+        // touch a so b is the oldest
+        assert_eq!(memo.get(&'a'), Some(1));
+        memo.insert('c', 3);
+        assert_eq!(memo.peek(&'b'), None);
+        assert_eq!(memo.peek(&'a'), Some(&1));
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.stats().evictions, 1);
+        // overwriting an existing key never evicts
+        memo.insert('c', 4);
+        assert_eq!(memo.stats().evictions, 1);
+        assert_eq!(memo.get(&'c'), Some(4));
+        // and leaves nothing stale behind to evict later
+        let mut memo = Memo::bounded(2);
+        for k in ['a', 'b', 'b', 'c', 'd', 'e'] {
+            memo.insert(k, 0);
+            assert!(memo.len() <= 2);
+        }
+        assert_eq!(memo.stats().evictions, 3);
+        assert_eq!((memo.peek(&'d'), memo.peek(&'e')), (Some(&0), Some(&0)));
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(memo.get(&'a'), None);
+    }
+
+    #[test]
+    fn test_memo_fn() {
+        let mut sq = MemoFn::new(|x: &u64| x * x);
+        assert_eq!(sq.call(&3), 9);
+        assert_eq!(sq.call(&3), 9);
+        assert_eq!(sq.call(&4), 16);
+        let s = sq.stats();
+        assert_eq!((s.hits, s.misses), (1, 2));
+        let mut one = MemoFn::bounded(1, |x: &u64| x + 1);
+        assert_eq!(one.call(&1), 2);
+        assert_eq!(one.call(&2), 3);
+        assert_eq!(one.stats().evictions, 1);
+    }
+}
@@ -0,0 +1,466 @@
+// Small parser combinators over a cursor into the input. A parser is any
+// Fn(&mut Cursor) -> PResult<T>, so a day can declare its grammar with the
+// helpers below and get an error with a line and column when the input
+// does not match instead of silently skipping it.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    // both 1-based
+    pub line: usize,
+    pub col: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} col {}: expected {}, found {}", self.line, self.col, self.expected, self.found)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub type PResult<T> = Result<T, ParseError>;
+
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    src: &'a str,
+    pos: usize,
+    // line number of the start of src, so a single line cut out of a
+    // bigger file still reports where it came from
+    first_line: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Cursor { src, pos: 0, first_line: 1 }
+    }
+
+    // a cursor over one line (or section) that starts at line `line` of the file
+    pub fn at_line(src: &'a str, line: usize) -> Self {
+        Cursor { src, pos: 0, first_line: line }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    pub fn advance(&mut self, n: usize) {
+        self.pos += n;
+    }
+
+    // (line, col) of the current position, both 1-based
+    pub fn location(&self) -> (usize, usize) {
+        let before = &self.src[..self.pos];
+        let line = self.first_line + before.matches('\n').count();
+        let col = match before.rfind('\n') {
+            Some(i) => before[i + 1..].chars().count() + 1,
+            None => before.chars().count() + 1,
+        };
+        (line, col)
+    }
+
+    pub fn error(&self, expected: impl Into<String>) -> ParseError {
+        let (line, col) = self.location();
+        let found = match self.peek() {
+            None => "end of input".to_string(),
+            Some('\n') => "end of line".to_string(),
+            Some(_) => format!("{:?}", self.rest().chars().take(10).collect::<String>()),
+        };
+        ParseError { line, col, expected: expected.into(), found }
+    }
+
+    // consume the longest prefix matching f
+    pub fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let n = rest.find(|c: char| !f(c)).unwrap_or(rest.len());
+        self.pos += n;
+        &rest[..n]
+    }
+}
+
+// run p over the whole of src, anything left over is an error
+pub fn parse_all<'a, T>(src: &'a str, line: usize, p: impl Fn(&mut Cursor<'a>) -> PResult<T>) -> PResult<T> {
+    let mut c = Cursor::at_line(src, line);
+    let v = p(&mut c)?;
+    eof()(&mut c)?;
+    Ok(v)
+}
+
+pub fn tag<'a>(t: &'static str) -> impl Fn(&mut Cursor<'a>) -> PResult<&'a str> {
+    move |c: &mut Cursor<'a>| {
+        if c.rest().starts_with(t) {
+            let s = &c.rest()[..t.len()];
+            c.advance(t.len());
+            Ok(s)
+        } else {
+            Err(c.error(format!("{:?}", t)))
+        }
+    }
+}
+
+// a single char from the set
+pub fn one_of<'a>(set: &'static str) -> impl Fn(&mut Cursor<'a>) -> PResult<char> {
+    move |c: &mut Cursor<'a>| match c.peek() {
+        Some(x) if set.contains(x) => {
+            c.advance(x.len_utf8());
+            Ok(x)
+        }
+        _ => Err(c.error(format!("one of {:?}", set))),
+    }
+}
+
+pub fn eof<'a>() -> impl Fn(&mut Cursor<'a>) -> PResult<()> {
+    |c: &mut Cursor<'a>| if c.is_eof() { Ok(()) } else { Err(c.error("end of input")) }
+}
+
+// one or more ascii digits, left as text
+pub fn digits<'a>() -> impl Fn(&mut Cursor<'a>) -> PResult<&'a str> {
+    |c: &mut Cursor<'a>| {
+        let d = c.take_while(|x| x.is_ascii_digit());
+        if d.is_empty() { Err(c.error("a digit")) } else { Ok(d) }
+    }
+}
+
+// an unsigned integer that must fit in T
+pub fn uint<'a, T: FromStr>() -> impl Fn(&mut Cursor<'a>) -> PResult<T> {
+    |c: &mut Cursor<'a>| {
+        let start = c.clone();
+        let d = digits()(c)?;
+        d.parse::<T>().map_err(|_| start.error(format!("an integer that fits in {}", std::any::type_name::<T>())))
+    }
+}
+
+// an integer with an optional leading + or -
+pub fn int<'a, T: FromStr>() -> impl Fn(&mut Cursor<'a>) -> PResult<T> {
+    |c: &mut Cursor<'a>| {
+        let start = c.clone();
+        let from = c.pos();
+        let _ = opt(one_of("+-"))(c)?;
+        digits()(c)?;
+        let text = &start.src[from..c.pos()];
+        text.trim_start_matches('+')
+            .parse::<T>()
+            .map_err(|_| start.error(format!("an integer that fits in {}", std::any::type_name::<T>())))
+    }
+}
+
+// letters, digits and underscores
+pub fn ident<'a>() -> impl Fn(&mut Cursor<'a>) -> PResult<&'a str> {
+    |c: &mut Cursor<'a>| {
+        let w = c.take_while(|x| x.is_ascii_alphanumeric() || x == '_');
+        if w.is_empty() { Err(c.error("an identifier")) } else { Ok(w) }
+    }
+}
+
+// zero or more spaces or tabs
+pub fn spaces<'a>() -> impl Fn(&mut Cursor<'a>) -> PResult<&'a str> {
+    |c: &mut Cursor<'a>| Ok(c.take_while(|x| x == ' ' || x == '\t'))
+}
+
+// one or more spaces or tabs
+pub fn spaces1<'a>() -> impl Fn(&mut Cursor<'a>) -> PResult<&'a str> {
+    |c: &mut Cursor<'a>| {
+        let s = spaces()(c)?;
+        if s.is_empty() { Err(c.error("a space")) } else { Ok(s) }
+    }
+}
+
+// Try p and rewind on failure. Only errors raised at the starting
+// position count as "not there", a failure part way through is real.
+pub fn opt<'a, T>(p: impl Fn(&mut Cursor<'a>) -> PResult<T>) -> impl Fn(&mut Cursor<'a>) -> PResult<Option<T>> {
+    move |c: &mut Cursor<'a>| {
+        let start = c.pos();
+        match p(c) {
+            Ok(v) => Ok(Some(v)),
+            Err(_) if c.pos() == start => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+pub fn alt<'a, T>(
+    a: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+    b: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<T> {
+    move |c: &mut Cursor<'a>| {
+        let saved = c.clone();
+        match a(c) {
+            Ok(v) => Ok(v),
+            Err(ea) => {
+                *c = saved;
+                b(c).map_err(|eb| ParseError { expected: format!("{} or {}", ea.expected, eb.expected), ..eb })
+            }
+        }
+    }
+}
+
+pub fn map<'a, T, U>(
+    p: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+    f: impl Fn(T) -> U,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<U> {
+    move |c: &mut Cursor<'a>| p(c).map(&f)
+}
+
+pub fn preceded<'a, A, T>(
+    a: impl Fn(&mut Cursor<'a>) -> PResult<A>,
+    p: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<T> {
+    move |c: &mut Cursor<'a>| {
+        a(c)?;
+        p(c)
+    }
+}
+
+pub fn terminated<'a, T, B>(
+    p: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+    b: impl Fn(&mut Cursor<'a>) -> PResult<B>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<T> {
+    move |c: &mut Cursor<'a>| {
+        let v = p(c)?;
+        b(c)?;
+        Ok(v)
+    }
+}
+
+pub fn delimited<'a, A, T, B>(
+    open: impl Fn(&mut Cursor<'a>) -> PResult<A>,
+    p: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+    close: impl Fn(&mut Cursor<'a>) -> PResult<B>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<T> {
+    move |c: &mut Cursor<'a>| {
+        open(c)?;
+        let v = p(c)?;
+        close(c)?;
+        Ok(v)
+    }
+}
+
+pub fn pair<'a, A, S, B>(
+    a: impl Fn(&mut Cursor<'a>) -> PResult<A>,
+    sep: impl Fn(&mut Cursor<'a>) -> PResult<S>,
+    b: impl Fn(&mut Cursor<'a>) -> PResult<B>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<(A, B)> {
+    move |c: &mut Cursor<'a>| {
+        let x = a(c)?;
+        sep(c)?;
+        let y = b(c)?;
+        Ok((x, y))
+    }
+}
+
+// Zero or more. A match that consumes nothing would match forever, so
+// it ends the repetition and is not kept.
+pub fn many<'a, T>(p: impl Fn(&mut Cursor<'a>) -> PResult<T>) -> impl Fn(&mut Cursor<'a>) -> PResult<Vec<T>> {
+    move |c: &mut Cursor<'a>| {
+        let mut out = Vec::new();
+        repeat(&p, c, &mut out)?;
+        Ok(out)
+    }
+}
+
+// One or more, p has to match at least once. That first match is kept
+// even if it consumes nothing, after it repetition ends as in many.
+pub fn many1<'a, T>(p: impl Fn(&mut Cursor<'a>) -> PResult<T>) -> impl Fn(&mut Cursor<'a>) -> PResult<Vec<T>> {
+    move |c: &mut Cursor<'a>| {
+        let mut out = vec![p(c)?];
+        repeat(&p, c, &mut out)?;
+        Ok(out)
+    }
+}
+
+fn repeat<'a, T>(p: impl Fn(&mut Cursor<'a>) -> PResult<T>, c: &mut Cursor<'a>, out: &mut Vec<T>) -> PResult<()> {
+    loop {
+        let from = c.pos();
+        match opt(&p)(c)? {
+            Some(v) if c.pos() > from => out.push(v),
+            _ => return Ok(()),
+        }
+    }
+}
+
+// one or more p separated by sep
+pub fn sep_by1<'a, T, S>(
+    p: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+    sep: impl Fn(&mut Cursor<'a>) -> PResult<S>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<Vec<T>> {
+    move |c: &mut Cursor<'a>| {
+        let mut out = vec![p(c)?];
+        while opt(&sep)(c)?.is_some() {
+            out.push(p(c)?);
+        }
+        Ok(out)
+    }
+}
+
+// zero or more p separated by sep
+pub fn sep_by<'a, T, S>(
+    p: impl Fn(&mut Cursor<'a>) -> PResult<T>,
+    sep: impl Fn(&mut Cursor<'a>) -> PResult<S>,
+) -> impl Fn(&mut Cursor<'a>) -> PResult<Vec<T>> {
+    let list = sep_by1(p, sep);
+    move |c: &mut Cursor<'a>| Ok(opt(&list)(c)?.unwrap_or_default())
+}
+
+// A block of lines separated from the next by a blank line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section<'a> {
+    // 1-based line number of the first line
+    pub line: usize,
+    pub text: &'a str,
+}
+
+impl<'a> Section<'a> {
+    // every line with its line number, none are blank since a blank line
+    // ends the section
+    pub fn lines(&self) -> impl Iterator<Item = (usize, &'a str)> {
+        let first = self.line;
+        self.text.lines().enumerate().map(move |(i, l)| (first + i, l))
+    }
+}
+
+pub fn sections(src: &str) -> Vec<Section<'_>> {
+    let mut out = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for (i, l) in src.split_inclusive('\n').enumerate() {
+        let blank = l.trim().is_empty();
+        match (blank, start) {
+            (false, None) => start = Some((i + 1, offset)),
+            (true, Some((line, from))) => {
+                out.push(Section { line, text: src[from..offset].trim_end_matches(['\r', '\n']) });
+                start = None;
+            }
+            _ => (),
+        }
+        offset += l.len();
+    }
+    if let Some((line, from)) = start {
+        out.push(Section { line, text: src[from..].trim_end_matches(['\r', '\n']) });
+    }
+    out
+}
+
+// Fixed width columns: the byte ranges between runs of positions that
+// are blank on every line. Short lines count as blank past their end.
+pub fn columns(lines: &[&str]) -> Vec<Range<usize>> {
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    let blank = |i: usize| lines.iter().all(|l| l.as_bytes().get(i).is_none_or(|&b| b == b' '));
+    let mut out = Vec::new();
+    let mut start: Option<usize> = None;
+    for i in 0..width {
+        match (blank(i), start) {
+            (false, None) => start = Some(i),
+            (true, Some(s)) => {
+                out.push(s..i);
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        out.push(s..width);
+    }
+    out
+}
+
+// the part of line inside r, short lines give a short or empty slice
+pub fn cut<'a>(line: &'a str, r: &Range<usize>) -> &'a str {
+    let end = r.end.min(line.len());
+    if r.start >= end { "" } else { &line[r.start..end] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list() {
+        let p = delimited(tag("{"), sep_by1(uint::<u16>(), tag(",")), tag("}"));
+        assert_eq!(parse_all("{3,5,4,7}", 1, &p), Ok(vec![3, 5, 4, 7]));
+        let e = parse_all("{3,5,,7}", 4, &p).unwrap_err();
+        assert_eq!((e.line, e.col), (4, 6));
+        assert_eq!(e.expected, "a digit");
+        let p = delimited(tag("{"), sep_by(uint::<u16>(), tag(",")), tag("}"));
+        assert_eq!(parse_all("{}", 1, &p), Ok(vec![]));
+        assert_eq!(parse_all("{7}", 1, &p), Ok(vec![7]));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let e = parse_all("70000", 1, uint::<u16>()).unwrap_err();
+        assert_eq!(e.col, 1);
+        assert!(e.expected.contains("u16"));
+        assert_eq!(parse_all("-12", 1, int::<i32>()), Ok(-12));
+        assert_eq!(parse_all("+12", 1, int::<i32>()), Ok(12));
+    }
+
+    #[test]
+    fn test_trailing_input() {
+        let e = parse_all("12x", 1, uint::<u8>()).unwrap_err();
+        assert_eq!(e.col, 3);
+        assert_eq!(e.expected, "end of input");
+    }
+
+    #[test]
+    fn test_many_and_alt() {
+        let word = preceded(spaces1(), ident());
+        let p = pair(ident(), tag(":"), many(word));
+        assert_eq!(parse_all("aaa: you hhh", 1, &p), Ok(("aaa", vec!["you", "hhh"])));
+        let sign = alt(map(tag("L"), |_| -1), map(tag("R"), |_| 1));
+        assert_eq!(parse_all("R", 1, &sign), Ok(1));
+        let e = parse_all("X", 1, &sign).unwrap_err();
+        assert_eq!(e.expected, "\"L\" or \"R\"");
+        // spaces() always matches, even with nothing to take
+        assert_eq!(parse_all("", 1, many(spaces())), Ok(vec![]));
+        assert_eq!(parse_all(" ", 1, many1(spaces())), Ok(vec![" "]));
+        assert_eq!(parse_all("", 1, many1(spaces())), Ok(vec![""]));
+        assert!(parse_all("", 1, many1(spaces1())).is_err());
+        let p = terminated(many(opt(tag("a"))), eof());
+        assert_eq!(parse_all("aa", 1, p), Ok(vec![Some("a"), Some("a")]));
+    }
+
+    #[test]
+    fn test_location() {
+        let mut c = Cursor::new("ab\ncd");
+        c.advance(4);
+        assert_eq!(c.location(), (2, 2));
+    }
+
+    #[test]
+    fn test_sections() {
+        let s = sections("a\nb\n\n\nc\n");
+        assert_eq!(s.len(), 2);
+        assert_eq!(s[0], Section { line: 1, text: "a\nb" });
+        assert_eq!(s[1], Section { line: 5, text: "c" });
+        assert_eq!(s[1].lines().collect::<Vec<_>>(), vec![(5, "c")]);
+        let s = sections("a\r\n \r\nb\r\nc  \r\n");
+        assert_eq!(s[0].lines().collect::<Vec<_>>(), vec![(1, "a")]);
+        assert_eq!(s[1].lines().collect::<Vec<_>>(), vec![(3, "b"), (4, "c  ")]);
+    }
+
+    #[test]
+    fn test_columns() {
+        let lines = ["123 328  51", " 45 64  387", "*   +   *"];
+        let cols = columns(&lines);
+        assert_eq!(cols, vec![0..3, 4..7, 8..11]);
+        assert_eq!(cut(lines[2], &cols[2]), "*");
+        assert_eq!(cut(lines[1], &cols[0]), " 45");
+    }
+}
@@ -0,0 +1,357 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+// A puzzle state space. A day implements this once and can then pick
+// whichever search fits the puzzle.
+//
+// heuristic must never overestimate the remaining cost or astar and
+// ida_star stop being optimal. The default of zero is always admissible.
+// It need not be consistent, astar expands a state again when it finds a
+// cheaper way there, which only costs time when h is inconsistent.
+pub trait SearchProblem {
+    type State: Clone + Eq + Hash;
+
+    fn start(&self) -> Self::State;
+
+    // every state reachable in one step along with the cost of the step
+    fn successors(&self, state: &Self::State) -> Vec<(Self::State, u64)>;
+
+    fn is_goal(&self, state: &Self::State) -> bool;
+
+    fn heuristic(&self, _state: &Self::State) -> u64 {
+        0
+    }
+
+    // return true to never expand this state, e.g. a counter went over target
+    fn prune(&self, _state: &Self::State) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    pub expanded: usize,
+    pub generated: usize,
+    pub pruned: usize,
+    pub max_frontier: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome<S> {
+    // None if the goal is unreachable
+    pub cost: Option<u64>,
+    // start to goal inclusive, empty if the goal is unreachable
+    pub path: Vec<S>,
+    pub stats: Stats,
+}
+
+impl<S> Outcome<S> {
+    fn failed(stats: Stats) -> Self {
+        Outcome { cost: None, path: Vec::new(), stats }
+    }
+
+    // number of steps taken, handy when every step costs one
+    pub fn steps(&self) -> Option<usize> {
+        self.cost.map(|_| self.path.len() - 1)
+    }
+}
+
+fn rebuild<S: Clone + Eq + Hash>(parents: &HashMap<S, S>, goal: S) -> Vec<S> {
+    let mut path = vec![goal];
+    while let Some(p) = parents.get(&path[path.len() - 1]) {
+        path.push(p.clone());
+    }
+    path.reverse();
+    path
+}
+
+// Breadth first by number of steps. The cost reported is the sum of the
+// step costs along the path found, which is only optimal when all steps
+// cost the same.
+pub fn bfs<P: SearchProblem>(problem: &P) -> Outcome<P::State> {
+    let mut stats = Stats::default();
+    let start = problem.start();
+    let mut parents: HashMap<P::State, P::State> = HashMap::new();
+    let mut costs: HashMap<P::State, u64> = HashMap::new();
+    let mut seen: HashSet<P::State> = HashSet::from([start.clone()]);
+    let mut frontier: VecDeque<P::State> = VecDeque::from([start.clone()]);
+    costs.insert(start, 0);
+
+    while let Some(s) = frontier.pop_front() {
+        if problem.is_goal(&s) {
+            let cost = costs[&s];
+            return Outcome { cost: Some(cost), path: rebuild(&parents, s), stats };
+        }
+        if problem.prune(&s) {
+            stats.pruned += 1;
+            continue;
+        }
+        stats.expanded += 1;
+        for (n, c) in problem.successors(&s) {
+            stats.generated += 1;
+            if seen.insert(n.clone()) {
+                costs.insert(n.clone(), costs[&s] + c);
+                parents.insert(n.clone(), s.clone());
+                frontier.push_back(n);
+            }
+        }
+        stats.max_frontier = stats.max_frontier.max(frontier.len());
+    }
+    Outcome::failed(stats)
+}
+
+fn best_first<P: SearchProblem>(problem: &P, informed: bool) -> Outcome<P::State> {
+    let mut stats = Stats::default();
+    let start = problem.start();
+    let h = |s: &P::State| if informed { problem.heuristic(s) } else { 0 };
+
+    // states are not Ord so the heap holds an index into `states`
+    let mut states: Vec<P::State> = vec![start.clone()];
+    let mut best: HashMap<P::State, u64> = HashMap::from([(start.clone(), 0)]);
+    let mut parents: HashMap<P::State, P::State> = HashMap::new();
+    let mut heap: BinaryHeap<Reverse<(u64, u64, usize)>> = BinaryHeap::new();
+    heap.push(Reverse((h(&start), 0, 0)));
+
+    while let Some(Reverse((_, g, idx))) = heap.pop() {
+        let s = states[idx].clone();
+        if g > best[&s] {
+            // stale entry, a cheaper route was found after this was queued
+            continue;
+        }
+        if problem.is_goal(&s) {
+            return Outcome { cost: Some(g), path: rebuild(&parents, s), stats };
+        }
+        if problem.prune(&s) {
+            stats.pruned += 1;
+            continue;
+        }
+        stats.expanded += 1;
+        for (n, c) in problem.successors(&s) {
+            stats.generated += 1;
+            let ng = g + c;
+            // an expanded state that turns up cheaper goes back on the heap
+            match best.get(&n) {
+                Some(&old) if old <= ng => continue,
+                _ => (),
+            }
+            best.insert(n.clone(), ng);
+            parents.insert(n.clone(), s.clone());
+            heap.push(Reverse((ng + h(&n), ng, states.len())));
+            states.push(n);
+        }
+        stats.max_frontier = stats.max_frontier.max(heap.len());
+    }
+    Outcome::failed(stats)
+}
+
+// Uniform cost search, ignores the heuristic.
+pub fn dijkstra<P: SearchProblem>(problem: &P) -> Outcome<P::State> {
+    best_first(problem, false)
+}
+
+pub fn astar<P: SearchProblem>(problem: &P) -> Outcome<P::State> {
+    best_first(problem, true)
+}
+
+// Iterative deepening A*. Uses memory proportional to the path length
+// instead of the state space, at the price of re-expanding states.
+pub fn ida_star<P: SearchProblem>(problem: &P) -> Outcome<P::State> {
+    enum Step {
+        Found(u64),
+        Next(Option<u64>),
+    }
+
+    fn descend<P: SearchProblem>(
+        problem: &P,
+        path: &mut Vec<P::State>,
+        on_path: &mut HashSet<P::State>,
+        g: u64,
+        bound: u64,
+        stats: &mut Stats,
+    ) -> Step {
+        let s = path[path.len() - 1].clone();
+        let f = g + problem.heuristic(&s);
+        if f > bound {
+            return Step::Next(Some(f));
+        }
+        if problem.is_goal(&s) {
+            return Step::Found(g);
+        }
+        if problem.prune(&s) {
+            stats.pruned += 1;
+            return Step::Next(None);
+        }
+        stats.expanded += 1;
+        let mut next: Option<u64> = None;
+        for (n, c) in problem.successors(&s) {
+            stats.generated += 1;
+            // only avoid cycles along the current path
+            if on_path.contains(&n) {
+                continue;
+            }
+            on_path.insert(n.clone());
+            path.push(n.clone());
+            stats.max_frontier = stats.max_frontier.max(path.len());
+            match descend(problem, path, on_path, g + c, bound, stats) {
+                Step::Found(cost) => return Step::Found(cost),
+                Step::Next(Some(t)) => next = Some(next.map_or(t, |x| x.min(t))),
+                Step::Next(None) => (),
+            }
+            path.pop();
+            on_path.remove(&n);
+        }
+        Step::Next(next)
+    }
+
+    let mut stats = Stats::default();
+    let start = problem.start();
+    let mut bound = problem.heuristic(&start);
+    let mut path = vec![start.clone()];
+    let mut on_path = HashSet::from([start]);
+
+    loop {
+        match descend(problem, &mut path, &mut on_path, 0, bound, &mut stats) {
+            Step::Found(cost) => return Outcome { cost: Some(cost), path, stats },
+            Step::Next(Some(t)) => bound = t,
+            Step::Next(None) => return Outcome::failed(stats),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a small weighted graph where the fewest steps is not the cheapest
+    // a -1-> b -1-> c -1-> d and a -5-> d
+    struct Weighted {
+        edges: HashMap<char, Vec<(char, u64)>>,
+    }
+
+    impl Weighted {
+        fn new() -> Self {
+            let mut edges = HashMap::new();
+            edges.insert('a', vec![('d', 5), ('b', 1)]);
+            edges.insert('b', vec![('c', 1)]);
+            edges.insert('c', vec![('d', 1)]);
+            Weighted { edges }
+        }
+    }
+
+    impl SearchProblem for Weighted {
+        type State = char;
+        fn start(&self) -> char {
+            'a'
+        }
+        fn successors(&self, s: &char) -> Vec<(char, u64)> {
+            self.edges.get(s).cloned().unwrap_or_default()
+        }
+        fn is_goal(&self, s: &char) -> bool {
+            *s == 'd'
+        }
+    }
+
+    // walk a number line from 0 to target by +1 or +3 with a distance heuristic
+    struct Line {
+        target: i64,
+    }
+
+    impl SearchProblem for Line {
+        type State = i64;
+        fn start(&self) -> i64 {
+            0
+        }
+        fn successors(&self, s: &i64) -> Vec<(i64, u64)> {
+            vec![(s + 1, 1), (s + 3, 1)]
+        }
+        fn is_goal(&self, s: &i64) -> bool {
+            *s == self.target
+        }
+        fn heuristic(&self, s: &i64) -> u64 {
+            ((self.target - s).max(0) as u64).div_ceil(3)
+        }
+        fn prune(&self, s: &i64) -> bool {
+            *s > self.target
+        }
+    }
+
+    #[test]
+    fn test_bfs_fewest_steps() {
+        let o = bfs(&Weighted::new());
+        assert_eq!(o.path, vec!['a', 'd']);
+        assert_eq!(o.cost, Some(5));
+        assert_eq!(o.steps(), Some(1));
+    }
+
+    #[test]
+    fn test_dijkstra_cheapest() {
+        let o = dijkstra(&Weighted::new());
+        assert_eq!(o.path, vec!['a', 'b', 'c', 'd']);
+        assert_eq!(o.cost, Some(3));
+    }
+
+    #[test]
+    fn test_astar_matches_dijkstra() {
+        let p = Line { target: 11 };
+        let a = astar(&p);
+        let d = dijkstra(&p);
+        assert_eq!(a.cost, Some(5));
+        assert_eq!(a.cost, d.cost);
+        assert!(a.stats.expanded <= d.stats.expanded);
+    }
+
+    // s -1-> a -1-> b -3-> g and s -3-> b. h(a) = 4 is exact but more than
+    // the step to b plus h(b), so b is first reached the expensive way.
+    struct Inconsistent;
+
+    impl SearchProblem for Inconsistent {
+        type State = char;
+        fn start(&self) -> char {
+            's'
+        }
+        fn successors(&self, s: &char) -> Vec<(char, u64)> {
+            match s {
+                's' => vec![('a', 1), ('b', 3)],
+                'a' => vec![('b', 1)],
+                'b' => vec![('g', 3)],
+                _ => vec![],
+            }
+        }
+        fn is_goal(&self, s: &char) -> bool {
+            *s == 'g'
+        }
+        fn heuristic(&self, s: &char) -> u64 {
+            if *s == 'a' { 4 } else { 0 }
+        }
+    }
+
+    #[test]
+    fn test_astar_reopens() {
+        let o = astar(&Inconsistent);
+        assert_eq!(o.cost, Some(5));
+        assert_eq!(o.path, vec!['s', 'a', 'b', 'g']);
+        // b is expanded twice
+        assert_eq!(o.stats.expanded, 4);
+        assert_eq!(ida_star(&Inconsistent).cost, Some(5));
+    }
+
+    #[test]
+    fn test_ida_star() {
+        let o = ida_star(&Line { target: 11 });
+        assert_eq!(o.cost, Some(5));
+        assert_eq!(o.path[0], 0);
+        assert_eq!(o.path[o.path.len() - 1], 11);
+        let o = ida_star(&Weighted::new());
+        assert_eq!(o.cost, Some(3));
+    }
+
+    #[test]
+    fn test_unreachable() {
+        let p = Line { target: -1 };
+        assert_eq!(bfs(&p).cost, None);
+        assert_eq!(astar(&p).cost, None);
+        assert_eq!(ida_star(&p).cost, None);
+        assert!(astar(&p).stats.pruned > 0);
+    }
+}
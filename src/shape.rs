@@ -0,0 +1,241 @@
+use std::fmt;
+
+use crate::parse::{PResult, many1, map, one_of, parse_all};
+
+pub type Cell = (i32, i32);
+
+// A polyomino as a set of (x, y) cells, always shifted so the smallest x
+// and y are 0 and sorted so equal shapes compare equal
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Shape {
+    cells: Vec<Cell>,
+}
+
+impl Shape {
+    pub fn new(cells: &[Cell]) -> Self {
+        let mx = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let my = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        let mut cells: Vec<Cell> = cells.iter().map(|&(x, y)| (x - mx, y - my)).collect();
+        cells.sort();
+        cells.dedup();
+        Shape { cells }
+    }
+
+    // A `#`/`.` picture, `line` is the line number of its first row so
+    // errors point into the file it came from
+    pub fn parse(src: &str, line: usize) -> PResult<Shape> {
+        let row = many1(map(one_of("#."), |c| c == '#'));
+        let mut cells = Vec::new();
+        for (y, text) in src.lines().enumerate() {
+            let bits = parse_all(text.trim_end(), line + y, &row)?;
+            cells.extend(bits.iter().enumerate().filter(|(_, b)| **b).map(|(x, _)| (x as i32, y as i32)));
+        }
+        Ok(Shape::new(&cells))
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+
+    // width and height of the bounding box
+    pub fn bbox(&self) -> (i32, i32) {
+        let w = self.cells.iter().map(|c| c.0).max().map_or(0, |m| m + 1);
+        let h = self.cells.iter().map(|c| c.1).max().map_or(0, |m| m + 1);
+        (w, h)
+    }
+
+    // the first cell in reading order
+    pub fn anchor(&self) -> Cell {
+        *self.cells.iter().min_by_key(|&&(x, y)| (y, x)).expect("empty shape")
+    }
+
+    pub fn rotate(&self) -> Shape {
+        Shape::new(&self.cells.iter().map(|&(x, y)| (-y, x)).collect::<Vec<_>>())
+    }
+
+    pub fn flip(&self) -> Shape {
+        Shape::new(&self.cells.iter().map(|&(x, y)| (-x, y)).collect::<Vec<_>>())
+    }
+
+    // the four rotations followed by the four rotations of the mirror
+    // image, duplicates included
+    pub fn transforms(&self) -> [Shape; 8] {
+        let mut out: [Shape; 8] = Default::default();
+        let mut s = self.clone();
+        for (i, o) in out.iter_mut().enumerate() {
+            if i == 4 {
+                s = s.flip();
+            }
+            *o = s.clone();
+            s = s.rotate();
+        }
+        out
+    }
+
+    // the distinct transforms
+    pub fn orientations(&self) -> Vec<Shape> {
+        let mut out: Vec<Shape> = self.transforms().into();
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    // the same for every transform of a shape, so two shapes are the same
+    // piece exactly when their canonical forms are equal
+    pub fn canonical(&self) -> Shape {
+        self.transforms().into_iter().min().unwrap()
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (w, h) = self.bbox();
+        for y in 0..h {
+            let row: String = (0..w).map(|x| if self.cells.binary_search(&(x, y)).is_ok() { '#' } else { '.' }).collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+// A rectangle of cells that shapes get placed on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    taken: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(width: usize, height: usize) -> Self {
+        Grid { width, height, taken: vec![false; width * height] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, (x, y): Cell) -> Option<usize> {
+        let inside = x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height;
+        inside.then(|| y as usize * self.width + x as usize)
+    }
+
+    // the cell at a reading order index
+    pub fn cell(&self, i: usize) -> Cell {
+        ((i % self.width) as i32, (i / self.width) as i32)
+    }
+
+    pub fn is_free(&self, c: Cell) -> bool {
+        self.index(c).is_some_and(|i| !self.taken[i])
+    }
+
+    pub fn set(&mut self, c: Cell, taken: bool) {
+        let i = self.index(c).expect("cell outside the grid");
+        self.taken[i] = taken;
+    }
+
+    pub fn free(&self) -> usize {
+        self.taken.iter().filter(|t| !**t).count()
+    }
+
+    // the first free cell at or after reading order index `from`
+    pub fn first_free(&self, from: usize) -> Option<usize> {
+        (from..self.taken.len()).find(|&i| !self.taken[i])
+    }
+
+    // true if the shape with its origin at `at` would hang off the grid
+    // or land on a taken cell
+    pub fn collides(&self, s: &Shape, at: Cell) -> bool {
+        s.cells.iter().any(|&(x, y)| !self.is_free((at.0 + x, at.1 + y)))
+    }
+
+    // place the shape if it fits, returns whether it did
+    pub fn place(&mut self, s: &Shape, at: Cell) -> bool {
+        if self.collides(s, at) {
+            return false;
+        }
+        for &(x, y) in &s.cells {
+            self.set((at.0 + x, at.1 + y), true);
+        }
+        true
+    }
+
+    pub fn remove(&mut self, s: &Shape, at: Cell) {
+        for &(x, y) in &s.cells {
+            self.set((at.0 + x, at.1 + y), false);
+        }
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.taken.chunks(self.width) {
+            let row: String = row.iter().map(|&t| if t { '#' } else { '.' }).collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let s = Shape::parse("..#\n.##\n", 1).unwrap();
+        assert_eq!(s.cells(), &[(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(s.bbox(), (2, 2));
+        assert_eq!(s.anchor(), (1, 0));
+        assert_eq!(s.to_string(), ".#\n##\n");
+        let e = Shape::parse("##\n#o\n", 5).unwrap_err();
+        assert_eq!((e.line, e.col), (6, 2));
+    }
+
+    #[test]
+    fn test_orientations() {
+        let l = Shape::parse("#.\n#.\n##\n", 1).unwrap();
+        assert_eq!(l.orientations().len(), 8);
+        let t = Shape::parse("###\n.#.\n", 1).unwrap();
+        assert_eq!(t.orientations().len(), 4);
+        let h = Shape::parse("###\n.#.\n###\n", 1).unwrap();
+        assert_eq!(h.orientations().len(), 2);
+        let o = Shape::parse("##\n##\n", 1).unwrap();
+        assert_eq!(o.orientations(), vec![o.clone()]);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let s = Shape::parse("##.\n.##\n", 1).unwrap();
+        let z = Shape::parse(".#\n##\n#.\n", 1).unwrap();
+        assert_eq!(s.canonical(), z.canonical());
+        assert!(s.transforms().iter().all(|t| t.canonical() == s.canonical()));
+        let l = Shape::parse("#.\n##\n", 1).unwrap();
+        assert_ne!(s.canonical(), l.canonical());
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut g = Grid::new(3, 2);
+        assert_eq!((g.width(), g.height()), (3, 2));
+        let l = Shape::parse("#.\n##\n", 1).unwrap();
+        assert!(g.place(&l, (0, 0)));
+        assert!(g.collides(&l, (0, 0)));
+        // off the right edge
+        assert!(!g.place(&l, (2, 0)));
+        assert!(g.place(&l.rotate().rotate(), (1, 0)));
+        assert_eq!(g.free(), 0);
+        assert_eq!(g.to_string(), "###\n###\n");
+        g.remove(&l, (0, 0));
+        assert_eq!(g.first_free(0), Some(0));
+        assert_eq!(g.cell(3), (0, 1));
+    }
+}
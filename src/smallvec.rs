@@ -0,0 +1,177 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+
+// A vector that keeps up to N items inline and only moves to the heap
+// once it grows past that. Search states are cloned and hashed constantly
+// so keeping the common small case off the heap matters.
+#[derive(Clone)]
+pub struct SmallVec<T: Copy + Default, const N: usize> {
+    store: Store<T, N>,
+}
+
+#[derive(Clone)]
+enum Store<T: Copy + Default, const N: usize> {
+    Inline(usize, [T; N]),
+    Heap(Vec<T>),
+}
+
+// a row of counters, e.g. the joltage levels of a d10 machine
+pub type Counters = SmallVec<u16, 16>;
+
+impl<T: Copy + Default, const N: usize> SmallVec<T, N> {
+    pub fn new() -> Self {
+        SmallVec { store: Store::Inline(0, [T::default(); N]) }
+    }
+
+    // n copies of the default value, e.g. a row of zeroed counters
+    pub fn zeroed(n: usize) -> Self {
+        let mut v = Self::new();
+        for _ in 0..n {
+            v.push(T::default());
+        }
+        v
+    }
+
+    pub fn push(&mut self, item: T) {
+        match &mut self.store {
+            Store::Inline(len, buf) if *len < N => {
+                buf[*len] = item;
+                *len += 1;
+            }
+            Store::Inline(len, buf) => {
+                let mut v = Vec::with_capacity(*len * 2 + 1);
+                v.extend_from_slice(&buf[..*len]);
+                v.push(item);
+                self.store = Store::Heap(v);
+            }
+            Store::Heap(v) => v.push(item),
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        match &mut self.store {
+            Store::Inline(0, _) => None,
+            Store::Inline(len, buf) => {
+                *len -= 1;
+                Some(buf[*len])
+            }
+            Store::Heap(v) => v.pop(),
+        }
+    }
+
+    pub fn spilled(&self) -> bool {
+        matches!(self.store, Store::Heap(_))
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.store {
+            Store::Inline(len, buf) => &buf[..*len],
+            Store::Heap(v) => v,
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.store {
+            Store::Inline(len, buf) => &mut buf[..*len],
+            Store::Heap(v) => v,
+        }
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for SmallVec<T, N> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> DerefMut for SmallVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+// equality and hashing only look at the live items so an inline vector
+// and a spilled one with the same contents are the same state
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for SmallVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Copy + Default + Eq, const N: usize> Eq for SmallVec<T, N> {}
+
+impl<T: Copy + Default + Hash, const N: usize> Hash for SmallVec<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state);
+    }
+}
+
+impl<T: Copy + Default + fmt::Debug, const N: usize> fmt::Debug for SmallVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> FromIterator<T> for SmallVec<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut v = Self::new();
+        for i in iter {
+            v.push(i);
+        }
+        v
+    }
+}
+
+impl<T: Copy + Default, const N: usize> From<&[T]> for SmallVec<T, N> {
+    fn from(item: &[T]) -> Self {
+        item.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_push_pop() {
+        let mut v = SmallVec::<u16, 2>::new();
+        v.push(1);
+        v.push(2);
+        assert!(!v.spilled());
+        v.push(3);
+        assert!(v.spilled());
+        assert_eq!(v.as_slice(), &[1, 2, 3]);
+        assert_eq!(v.pop(), Some(3));
+        assert_eq!(v.pop(), Some(2));
+        assert_eq!(v.pop(), Some(1));
+        assert_eq!(v.pop(), None);
+    }
+
+    #[test]
+    fn test_index_mut() {
+        let mut v = Counters::zeroed(4);
+        v[2] += 5;
+        assert_eq!(v.len(), 4);
+        assert_eq!(&v[..], &[0, 0, 5, 0]);
+    }
+
+    #[test]
+    fn test_spilled_eq_inline() {
+        let a: SmallVec<u16, 2> = [1, 2].as_slice().into();
+        let mut b: SmallVec<u16, 2> = [1, 2, 3].as_slice().into();
+        b.pop();
+        assert!(b.spilled());
+        assert_eq!(a, b);
+        let hs = HashSet::from([a]);
+        assert!(hs.contains(&b));
+    }
+}
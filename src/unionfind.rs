@@ -0,0 +1,166 @@
+// A successful union of two components
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Merge {
+    // the elements passed to union
+    pub a: usize,
+    pub b: usize,
+    // root and size of the combined component
+    pub root: usize,
+    pub size: usize,
+    // number of components left after the merge
+    pub components: usize,
+}
+
+type MergeFn = Box<dyn FnMut(&Merge)>;
+
+// Disjoint sets over 0..n with path compression and union by size
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    components: usize,
+    on_merge: Option<MergeFn>,
+}
+
+impl UnionFind {
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            components: n,
+            on_merge: None,
+        }
+    }
+
+    // called for every union that joins two different components
+    pub fn on_merge(&mut self, f: impl FnMut(&Merge) + 'static) {
+        self.on_merge = Some(Box::new(f));
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // point everything on the way straight at the root
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    // join the components of a and b, None if they were already joined
+    pub fn union(&mut self, a: usize, b: usize) -> Option<Merge> {
+        let mut ra = self.find(a);
+        let mut rb = self.find(b);
+        if ra == rb {
+            return None;
+        }
+        if self.size[ra] < self.size[rb] {
+            (ra, rb) = (rb, ra);
+        }
+        self.parent[rb] = ra;
+        self.size[ra] += self.size[rb];
+        self.components -= 1;
+
+        let m = Merge { a, b, root: ra, size: self.size[ra], components: self.components };
+        if let Some(f) = self.on_merge.as_mut() {
+            f(&m);
+        }
+        Some(m)
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    pub fn size_of(&mut self, x: usize) -> usize {
+        let r = self.find(x);
+        self.size[r]
+    }
+
+    // size of every component, in no particular order
+    pub fn sizes(&self) -> Vec<usize> {
+        (0..self.parent.len())
+            .filter(|&i| self.parent[i] == i)
+            .map(|i| self.size[i])
+            .collect()
+    }
+
+    // sizes of the k largest components, largest first
+    pub fn largest(&self, k: usize) -> Vec<usize> {
+        let mut s = self.sizes();
+        s.sort_unstable_by(|a, b| b.cmp(a));
+        s.truncate(k);
+        s
+    }
+}
+
+impl std::fmt::Debug for UnionFind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnionFind")
+            .field("len", &self.len())
+            .field("components", &self.components)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_union() {
+        let mut uf = UnionFind::new(5);
+        assert_eq!(uf.components(), 5);
+        let m = uf.union(0, 1).unwrap();
+        assert_eq!(m.size, 2);
+        assert_eq!(m.components, 4);
+        assert!(uf.connected(0, 1));
+        assert!(!uf.connected(0, 2));
+        assert_eq!(uf.union(1, 0), None);
+        assert_eq!(uf.components(), 4);
+    }
+
+    #[test]
+    fn test_sizes() {
+        let mut uf = UnionFind::new(7);
+        uf.union(0, 1);
+        uf.union(1, 2);
+        uf.union(3, 4);
+        assert_eq!(uf.size_of(2), 3);
+        assert_eq!(uf.largest(2), vec![3, 2]);
+        assert_eq!(uf.largest(10), vec![3, 2, 1, 1]);
+        assert_eq!(uf.sizes().into_iter().sum::<usize>(), 7);
+    }
+
+    #[test]
+    fn test_on_merge() {
+        let seen = Rc::new(RefCell::new(Vec::<Merge>::new()));
+        let log = seen.clone();
+        let mut uf = UnionFind::new(3);
+        uf.on_merge(move |m| log.borrow_mut().push(*m));
+        uf.union(0, 1);
+        uf.union(0, 1);
+        uf.union(2, 1);
+        let seen = seen.borrow();
+        assert_eq!(seen.len(), 2);
+        assert_eq!((seen[1].a, seen[1].b, seen[1].components), (2, 1, 1));
+    }
+}